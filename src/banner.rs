@@ -0,0 +1,259 @@
+//! Fonts for writing text in large letters, for banners and the like:
+//! a small built-in font made of block characters, and fonts loaded
+//! from FIGlet (`.flf`) files.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub struct Font {
+    height: usize,
+    glyphs: HashMap<char, Vec<String>>,
+    /// Used for any character the font has no glyph for.
+    fallback: Option<char>,
+    /// Characters the font only has in one case (e.g., the block font
+    /// only has capitals).
+    uppercase_only: bool,
+}
+
+impl Font {
+    /// A font three rows high, drawn with `█`, `▀` and `▄`. It covers
+    /// letters (lowercase letters come out as capitals), digits and
+    /// some punctuation.
+    pub fn block() -> Font {
+        let glyphs = BLOCK_GLYPHS
+            .iter()
+            .map(|&(ch, pixels)| (ch, half_blocks(pixels)))
+            .collect();
+        Font {
+            height: 3,
+            glyphs,
+            fallback: Some('?'),
+            uppercase_only: true,
+        }
+    }
+
+    /// Parses a FIGlet font from the contents of a `.flf` file. Letters
+    /// are placed side by side at their full width; the FIGlet rules
+    /// for squeezing them together ("smushing") are not applied.
+    pub fn from_figlet(source: &str) -> io::Result<Font> {
+        let mut lines = source.lines();
+        let header = lines.next().ok_or_else(|| invalid("empty font file"))?;
+        if !header.starts_with("flf2a") {
+            return Err(invalid("not a FIGlet font (missing `flf2a` signature)"));
+        }
+        let hardblank = header[5..]
+            .chars()
+            .next()
+            .ok_or_else(|| invalid("missing hardblank"))?;
+        let params: Vec<&str> = header[5 + hardblank.len_utf8()..]
+            .split_whitespace()
+            .collect();
+        let number = |index: usize, what: &str| -> io::Result<usize> {
+            params
+                .get(index)
+                .and_then(|p| p.parse().ok())
+                .ok_or_else(|| invalid(&format!("bad or missing {} in header", what)))
+        };
+        let height = number(0, "height")?;
+        let comment_lines = number(4, "comment line count")?;
+        for _ in 0..comment_lines {
+            lines.next();
+        }
+
+        let mut glyphs = HashMap::new();
+        let required = (32..127u32).chain(vec![196, 214, 220, 228, 246, 252, 223]);
+        for code in required {
+            let glyph = read_figlet_glyph(&mut lines, height, hardblank)?;
+            if let Some(ch) = std::char::from_u32(code) {
+                glyphs.insert(ch, glyph);
+            }
+        }
+
+        // any further characters are each preceded by a line giving
+        // their code
+        while let Some(tag) = lines.next() {
+            let code = match tag.split_whitespace().next() {
+                Some(code) => parse_code(code)?,
+                None => continue,
+            };
+            let glyph = read_figlet_glyph(&mut lines, height, hardblank)?;
+            if let Some(ch) = std::char::from_u32(code) {
+                glyphs.insert(ch, glyph);
+            }
+        }
+
+        Ok(Font {
+            height,
+            glyphs,
+            fallback: None,
+            uppercase_only: false,
+        })
+    }
+
+    /// Reads and parses a FIGlet font file; see `from_figlet`.
+    pub fn load_figlet<P: AsRef<Path>>(path: P) -> io::Result<Font> {
+        Font::from_figlet(&fs::read_to_string(path)?)
+    }
+
+    /// The number of rows each line of text takes up.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    fn glyph(&self, ch: char) -> Option<&Vec<String>> {
+        let ch = if self.uppercase_only {
+            ch.to_ascii_uppercase()
+        } else {
+            ch
+        };
+        self.glyphs
+            .get(&ch)
+            .or_else(|| self.fallback.and_then(|f| self.glyphs.get(&f)))
+    }
+
+    /// Renders `text` into `height()` rows. Characters the font has no
+    /// glyph for are skipped.
+    pub fn render(&self, text: &str) -> Vec<String> {
+        let mut rows = vec![String::new(); self.height];
+        for glyph in text.chars().filter_map(|ch| self.glyph(ch)) {
+            for (row, line) in rows.iter_mut().zip(glyph) {
+                row.push_str(line);
+            }
+        }
+        rows
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Parses a character code as written in a FIGlet font: decimal, or
+/// hexadecimal (`0x...`) or octal (`0...`).
+fn parse_code(code: &str) -> io::Result<u32> {
+    let parsed = if let Some(hex) = code.strip_prefix("0x").or_else(|| code.strip_prefix("0X")) {
+        u32::from_str_radix(hex, 16)
+    } else if code.len() > 1 && code.starts_with('0') {
+        u32::from_str_radix(&code[1..], 8)
+    } else {
+        code.parse()
+    };
+    // negative codes are allowed, but name characters we cannot use
+    parsed.or_else(|_| {
+        if code.starts_with('-') {
+            Ok(u32::MAX)
+        } else {
+            Err(invalid(&format!("bad character code `{}`", code)))
+        }
+    })
+}
+
+/// Reads the `height` lines of one glyph. Each line ends with one or
+/// more copies of an end mark (usually `@`), which we strip, and
+/// hardblanks become ordinary spaces. Lines are padded to the same
+/// width.
+fn read_figlet_glyph<'a, I>(
+    lines: &mut I,
+    height: usize,
+    hardblank: char,
+) -> io::Result<Vec<String>>
+where
+    I: Iterator<Item = &'a str>,
+{
+    let mut glyph = Vec::with_capacity(height);
+    for _ in 0..height {
+        let line = lines
+            .next()
+            .ok_or_else(|| invalid("font file ends in the middle of a character"))?;
+        let line = match line.chars().last() {
+            Some(end_mark) => line.trim_end_matches(end_mark),
+            None => line,
+        };
+        glyph.push(line.replace(hardblank, " "));
+    }
+    let width = glyph.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    for line in &mut glyph {
+        let padding = width - line.chars().count();
+        line.extend((0..padding).map(|_| ' '));
+    }
+    Ok(glyph)
+}
+
+/// Converts a bitmap, five pixels high, into three rows of half-block
+/// characters, with a blank column after it to space the letters out.
+fn half_blocks(pixels: [&str; 5]) -> Vec<String> {
+    let pixel = |row: usize, column: usize| {
+        pixels
+            .get(row)
+            .is_some_and(|r| r.as_bytes()[column] == b'#')
+    };
+    let width = pixels[0].len();
+    (0..3)
+        .map(|row| {
+            let mut line: String = (0..width)
+                .map(
+                    |column| match (pixel(2 * row, column), pixel(2 * row + 1, column)) {
+                        (true, true) => '█',
+                        (true, false) => '▀',
+                        (false, true) => '▄',
+                        (false, false) => ' ',
+                    },
+                )
+                .collect();
+            line.push(' ');
+            line
+        })
+        .collect()
+}
+
+#[rustfmt::skip]
+const BLOCK_GLYPHS: &[(char, [&str; 5])] = &[
+    ('A', [".#.", "#.#", "###", "#.#", "#.#"]),
+    ('B', ["##.", "#.#", "##.", "#.#", "##."]),
+    ('C', [".##", "#..", "#..", "#..", ".##"]),
+    ('D', ["##.", "#.#", "#.#", "#.#", "##."]),
+    ('E', ["###", "#..", "##.", "#..", "###"]),
+    ('F', ["###", "#..", "##.", "#..", "#.."]),
+    ('G', [".##", "#..", "#.#", "#.#", ".##"]),
+    ('H', ["#.#", "#.#", "###", "#.#", "#.#"]),
+    ('I', ["###", ".#.", ".#.", ".#.", "###"]),
+    ('J', ["..#", "..#", "..#", "#.#", ".#."]),
+    ('K', ["#.#", "#.#", "##.", "#.#", "#.#"]),
+    ('L', ["#..", "#..", "#..", "#..", "###"]),
+    ('M', ["#...#", "##.##", "#.#.#", "#...#", "#...#"]),
+    ('N', ["#..#", "##.#", "#.##", "#..#", "#..#"]),
+    ('O', [".#.", "#.#", "#.#", "#.#", ".#."]),
+    ('P', ["##.", "#.#", "##.", "#..", "#.."]),
+    ('Q', [".#.", "#.#", "#.#", "##.", ".##"]),
+    ('R', ["##.", "#.#", "##.", "#.#", "#.#"]),
+    ('S', [".##", "#..", ".#.", "..#", "##."]),
+    ('T', ["###", ".#.", ".#.", ".#.", ".#."]),
+    ('U', ["#.#", "#.#", "#.#", "#.#", "###"]),
+    ('V', ["#.#", "#.#", "#.#", "#.#", ".#."]),
+    ('W', ["#...#", "#...#", "#.#.#", "##.##", "#...#"]),
+    ('X', ["#.#", "#.#", ".#.", "#.#", "#.#"]),
+    ('Y', ["#.#", "#.#", ".#.", ".#.", ".#."]),
+    ('Z', ["###", "..#", ".#.", "#..", "###"]),
+    ('0', ["###", "#.#", "#.#", "#.#", "###"]),
+    ('1', [".#.", "##.", ".#.", ".#.", "###"]),
+    ('2', ["##.", "..#", ".#.", "#..", "###"]),
+    ('3', ["##.", "..#", ".#.", "..#", "##."]),
+    ('4', ["#.#", "#.#", "###", "..#", "..#"]),
+    ('5', ["###", "#..", "##.", "..#", "##."]),
+    ('6', [".##", "#..", "###", "#.#", "###"]),
+    ('7', ["###", "..#", ".#.", ".#.", ".#."]),
+    ('8', ["###", "#.#", "###", "#.#", "###"]),
+    ('9', ["###", "#.#", "###", "..#", "##."]),
+    (' ', ["..", "..", "..", "..", ".."]),
+    ('!', ["#", "#", "#", ".", "#"]),
+    ('?', ["##.", "..#", ".#.", "...", ".#."]),
+    ('.', [".", ".", ".", ".", "#"]),
+    (',', ["..", "..", "..", ".#", "#."]),
+    (':', [".", "#", ".", "#", "."]),
+    ('-', ["...", "...", "###", "...", "..."]),
+    ('_', ["...", "...", "...", "...", "###"]),
+    ('/', ["..#", "..#", ".#.", "#..", "#.."]),
+    ('\'', ["#", "#", ".", ".", "."]),
+];
@@ -0,0 +1,46 @@
+//! The errors reported by the fallible (`try_`) drawing methods, for
+//! callers who would rather not panic on bad input.
+
+use crate::Point;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CanvasError {
+    /// A cell past the right edge of the view (or, for a canvas that
+    /// can grow, past its maximum width).
+    OutOfBounds {
+        row: usize,
+        column: usize,
+        columns: usize,
+    },
+    /// A character that is not a box-drawing (or diagonal) character.
+    UnknownGlyph(char),
+    /// A shape that cannot be drawn, such as a polyline segment that
+    /// is neither horizontal nor vertical.
+    InvalidGeometry { from: Point, to: Point },
+}
+
+impl fmt::Display for CanvasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CanvasError::OutOfBounds {
+                row,
+                column,
+                columns,
+            } => write!(
+                f,
+                "cell ({}, {}) is outside a view {} columns wide",
+                row, column, columns
+            ),
+            CanvasError::UnknownGlyph(ch) => write!(f, "no lines for character {:?}", ch),
+            CanvasError::InvalidGeometry { from, to } => write!(
+                f,
+                "polyline segment from {:?} to {:?} is not horizontal or vertical",
+                from, to
+            ),
+        }
+    }
+}
+
+impl Error for CanvasError {}
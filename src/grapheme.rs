@@ -0,0 +1,97 @@
+//! The `Grapheme` type holds the contents of a single cell: one
+//! grapheme cluster, such as a letter with its combining marks, a flag
+//! or an emoji sequence. Almost all of these are short, so they are
+//! stored inline, falling back to the heap for the rare long one.
+
+use std::fmt::{Debug, Display, Error, Formatter};
+use std::ops::Deref;
+use std::str;
+
+const INLINE_CAPACITY: usize = 15;
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct Grapheme {
+    repr: Repr,
+}
+
+#[derive(Clone, PartialEq, Eq)]
+enum Repr {
+    Inline {
+        len: u8,
+        bytes: [u8; INLINE_CAPACITY],
+    },
+    Heap(Box<str>),
+}
+
+impl Grapheme {
+    /// Creates a grapheme from a string. This does not check that the
+    /// string is a single grapheme cluster; `write_chars` and friends
+    /// take care of splitting text up.
+    pub fn new(text: &str) -> Grapheme {
+        let repr = if text.len() <= INLINE_CAPACITY {
+            let mut bytes = [0; INLINE_CAPACITY];
+            bytes[..text.len()].copy_from_slice(text.as_bytes());
+            Repr::Inline {
+                len: text.len() as u8,
+                bytes,
+            }
+        } else {
+            Repr::Heap(text.into())
+        };
+        Grapheme { repr }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match &self.repr {
+            Repr::Inline { len, bytes } => {
+                // we only ever copy a whole `&str` in
+                str::from_utf8(&bytes[..*len as usize]).unwrap()
+            }
+            Repr::Heap(text) => text,
+        }
+    }
+
+    /// The first `char` of the grapheme (e.g., the base letter, without
+    /// any combining marks), or a space if it is empty.
+    pub fn first_char(&self) -> char {
+        self.as_str().chars().next().unwrap_or(' ')
+    }
+}
+
+impl Default for Grapheme {
+    fn default() -> Grapheme {
+        Grapheme::new("")
+    }
+}
+
+impl From<char> for Grapheme {
+    fn from(ch: char) -> Grapheme {
+        Grapheme::new(ch.encode_utf8(&mut [0; 4]))
+    }
+}
+
+impl<'a> From<&'a str> for Grapheme {
+    fn from(text: &'a str) -> Grapheme {
+        Grapheme::new(text)
+    }
+}
+
+impl Deref for Grapheme {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Display for Grapheme {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
+        Display::fmt(self.as_str(), fmt)
+    }
+}
+
+impl Debug for Grapheme {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
+        Debug::fmt(self.as_str(), fmt)
+    }
+}
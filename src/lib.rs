@@ -1,191 +1,1551 @@
 //! An "ASCII Canvas" allows us to draw lines and write text into a
-//! fixed-sized canvas and then convert that canvas into ASCII
-//! characters. ANSI styling is supported.
+//! canvas (which grows downwards, and to the right if allowed) and
+//! then convert that canvas into ASCII characters. ANSI styling is
+//! supported.
 
+use crate::line::{DOWN, FALLING, LEFT, RIGHT, RISING, SLASHES, UP};
 use crate::style::Style;
+use crate::text::text_width;
 use std::cmp;
 use std::iter::ExactSizeIterator;
 use std::ops::Range;
 use term::Terminal;
+use unicode_segmentation::UnicodeSegmentation;
 
+mod banner;
+mod error;
+mod grapheme;
+mod line;
+mod route;
 mod row;
 #[cfg(test)]
+#[allow(clippy::needless_borrows_for_generic_args)]
 mod test;
 #[cfg(test)]
+#[allow(clippy::assertions_on_constants)]
 mod test_util;
+mod text;
+mod transform;
 
 pub mod style;
 
+pub use self::banner::Font;
+pub use self::error::CanvasError;
+pub use self::grapheme::Grapheme;
+pub use self::line::{Charset, JunctionRule, LineCell, LineStyle, Marker, Pen};
 pub use self::row::Row;
+pub use self::text::{Alignment, ControlChars, Newline, Sanitize, Truncate, Wrap};
+pub use self::transform::Transform;
 
 ///////////////////////////////////////////////////////////////////////////
 
 /// AsciiView is a view onto an `AsciiCanvas` which potentially
 /// applies transformations along the way (e.g., shifting, adding
-/// styling information). Most of the main drawing methods for
-/// `AsciiCanvas` are defined as inherent methods on an `AsciiView`
-/// trait object.
+/// styling information). The main drawing methods are defined on
+/// the `AsciiViewExt` trait, which every view implements.
 pub trait AsciiView {
     fn columns(&self) -> usize;
     fn read_char(&mut self, row: usize, column: usize) -> char;
     fn write_char(&mut self, row: usize, column: usize, ch: char, style: Style);
-}
 
-impl<'a> dyn AsciiView + 'a {
-    fn add_box_dirs(&mut self, row: usize, column: usize, dirs: u8) {
-        let old_ch = self.read_char(row, column);
-        let new_ch = add_dirs(old_ch, dirs);
-        self.write_char(row, column, new_ch, Style::new());
+    /// The characters that will be used to render the canvas, for
+    /// those drawing methods that need to know (e.g., to pick between
+    /// `…` and `...`).
+    fn charset(&self) -> Charset {
+        Charset::Unicode
+    }
+
+    /// How the text-writing methods treat control characters, tabs and
+    /// newlines. By default, they are made harmless.
+    fn sanitize(&self) -> Sanitize {
+        Sanitize::default()
+    }
+
+    /// Checks that the given cell can be written without panicking. By
+    /// default, that means it lies within `columns()`.
+    fn check_bounds(&self, row: usize, column: usize) -> Result<(), CanvasError> {
+        let columns = self.columns();
+        if column < columns {
+            Ok(())
+        } else {
+            Err(CanvasError::OutOfBounds {
+                row,
+                column,
+                columns,
+            })
+        }
+    }
+
+    /// Makes room for `rows` more rows above, and `columns` more
+    /// columns to the left of, everything drawn so far, moving the
+    /// existing content down and right. Returns false, having done
+    /// nothing, if the view cannot grow that way (the default).
+    fn grow_up_left(&mut self, rows: usize, columns: usize) -> bool {
+        let _ = (rows, columns);
+        false
+    }
+
+    /// Reads the lines drawn through the given cell. By default, these
+    /// are recovered from the box-drawing character in the cell, but
+    /// `AsciiCanvas` keeps its lines apart from the text.
+    fn read_lines(&mut self, row: usize, column: usize) -> LineCell {
+        LineCell::from_char(self.read_char(row, column))
     }
 
+    /// Replaces the lines drawn through the given cell. By default,
+    /// this writes the corresponding box-drawing character.
+    fn write_lines(&mut self, row: usize, column: usize, lines: LineCell, style: Style) {
+        self.write_char(row, column, lines.to_char(), style)
+    }
+
+    /// Reads the whole grapheme cluster in the given cell. By default,
+    /// this is just the result of `read_char`.
+    fn read_grapheme(&mut self, row: usize, column: usize) -> Grapheme {
+        Grapheme::from(self.read_char(row, column))
+    }
+
+    /// Reads the style of the given cell: the style of its text, or of
+    /// its lines if no text was written there. By default, this is the
+    /// plain style.
+    fn read_style(&mut self, row: usize, column: usize) -> Style {
+        let _ = (row, column);
+        Style::new()
+    }
+
+    /// Writes a whole grapheme cluster (e.g., a letter and its
+    /// combining marks) into the given cell. By default, only the first
+    /// `char` is written.
+    fn write_grapheme(&mut self, row: usize, column: usize, grapheme: &str, style: Style) {
+        let ch = grapheme.chars().next().unwrap_or(' ');
+        self.write_char(row, column, ch, style)
+    }
+}
+
+/// The drawing methods, available on every `AsciiView` (including
+/// `AsciiCanvas` itself and trait objects) without any casting.
+pub trait AsciiViewExt: AsciiView {
     /// Draws a line for the given range of rows at the given column.
-    pub fn draw_vertical_line(&mut self, rows: Range<usize>, column: usize) {
-        let len = rows.len();
-        for (index, r) in rows.enumerate() {
-            let new_dirs = if index == 0 {
-                DOWN
-            } else if index == len - 1 {
-                UP
-            } else {
-                UP | DOWN
-            };
-            self.add_box_dirs(r, column, new_dirs);
+    fn draw_vertical_line(&mut self, rows: Range<usize>, column: usize) {
+        self.draw_vertical_line_with(rows, column, LineStyle::Light)
+    }
+
+    /// Like `draw_vertical_line`, but drawing with the given pen (or
+    /// just a `LineStyle`).
+    fn draw_vertical_line_with<P: Into<Pen>>(&mut self, rows: Range<usize>, column: usize, pen: P) {
+        let pen = pen.into();
+        if rows.start >= rows.end {
+            return;
+        }
+        let start = Point::new(rows.start, column);
+        let end = Point::new(rows.end - 1, column);
+        for (r, dirs) in segment_dirs(rows, UP, DOWN) {
+            add_box_dirs(self, r, column, dirs, pen);
         }
+        draw_markers(self, pen, start, UP, end, DOWN);
     }
 
     /// Draws a horizontal line along a given row for the given range
     /// of columns.
-    pub fn draw_horizontal_line(&mut self, row: usize, columns: Range<usize>) {
-        let len = columns.len();
-        for (index, c) in columns.enumerate() {
-            let new_dirs = if index == 0 {
-                RIGHT
-            } else if index == len - 1 {
-                LEFT
-            } else {
-                LEFT | RIGHT
+    fn draw_horizontal_line(&mut self, row: usize, columns: Range<usize>) {
+        self.draw_horizontal_line_with(row, columns, LineStyle::Light)
+    }
+
+    /// Like `draw_horizontal_line`, but drawing with the given pen (or
+    /// just a `LineStyle`).
+    fn draw_horizontal_line_with<P: Into<Pen>>(
+        &mut self,
+        row: usize,
+        columns: Range<usize>,
+        pen: P,
+    ) {
+        let pen = pen.into();
+        if columns.start >= columns.end {
+            return;
+        }
+        let start = Point::new(row, columns.start);
+        let end = Point::new(row, columns.end - 1);
+        for (c, dirs) in segment_dirs(columns, LEFT, RIGHT) {
+            add_box_dirs(self, row, c, dirs, pen);
+        }
+        draw_markers(self, pen, start, LEFT, end, RIGHT);
+    }
+
+    /// Adds the lines of a box-drawing character (or a diagonal or a
+    /// marker) to the given cell, joining them up with any lines
+    /// already there, so that `┌` drawn over `┘` gives `┼`. Any other
+    /// character adds nothing.
+    fn draw_box_char(&mut self, row: usize, column: usize, ch: char, style: Style) {
+        let lines = self.read_lines(row, column).merge(LineCell::from_char(ch));
+        self.write_lines(row, column, lines, style);
+    }
+
+    /// Draws a line between two points (inclusive), at any angle. Lines
+    /// at 45° are drawn with `/` or `\`; other slopes are approximated
+    /// by runs of `─` or `│` joined by `╱` or `╲`.
+    fn draw_line(&mut self, from: Point, to: Point) {
+        self.draw_line_with(from, to, LineStyle::Light)
+    }
+
+    /// Like `draw_line`, but drawing with the given pen (or just a
+    /// `LineStyle`). Note that there are no heavy or double diagonals.
+    fn draw_line_with<P: Into<Pen>>(&mut self, from: Point, to: Point, pen: P) {
+        let pen = pen.into();
+        if from.row == to.row && from.column <= to.column {
+            return self.draw_horizontal_line_with(from.row, from.column..to.column + 1, pen);
+        }
+        if from.column == to.column && from.row <= to.row {
+            return self.draw_vertical_line_with(from.row..to.row + 1, from.column, pen);
+        }
+        if from.row == to.row || from.column == to.column {
+            return self.draw_line_with(to, from, pen.reversed());
+        }
+
+        // lines at exactly 45° get plain slashes
+        let slashes = if from.row.abs_diff(to.row) == from.column.abs_diff(to.column) {
+            SLASHES
+        } else {
+            0
+        };
+        let points = bresenham(from, to);
+        for (index, point) in points.iter().enumerate() {
+            // each cell is drawn according to the step that leaves it
+            // (or, for the last cell, the step that enters it)
+            let (a, b) = match points.get(index + 1) {
+                Some(next) => (point, next),
+                None => (&points[index - 1], point),
             };
-            self.add_box_dirs(row, c, new_dirs);
+            let down = b.row > a.row;
+            let right = b.column > a.column;
+            if a.row == b.row {
+                add_box_dirs(self, point.row, point.column, LEFT | RIGHT, pen);
+            } else if a.column == b.column {
+                add_box_dirs(self, point.row, point.column, UP | DOWN, pen);
+            } else if down == right {
+                add_box_diagonal(self, point.row, point.column, FALLING | slashes, pen);
+            } else {
+                add_box_diagonal(self, point.row, point.column, RISING | slashes, pen);
+            }
         }
+
+        let n = points.len();
+        let start_dir = step_dir(points[1], points[0]);
+        let end_dir = step_dir(points[n - 2], points[n - 1]);
+        draw_markers(self, pen, from, start_dir, to, end_dir);
+    }
+
+    /// Draws a line through each of the given points in turn. Each
+    /// segment must be horizontal or vertical; at each bend, the
+    /// corner is joined up properly.
+    fn draw_polyline(&mut self, points: &[Point]) {
+        self.draw_polyline_with(points, LineStyle::Light)
+    }
+
+    /// Like `draw_polyline`, but drawing with the given pen (or just a
+    /// `LineStyle`).
+    fn draw_polyline_with<P: Into<Pen>>(&mut self, points: &[Point], pen: P) {
+        let pen = pen.into();
+        let cells = polyline_cells(points);
+        if cells.len() < 2 {
+            return;
+        }
+
+        // each cell connects to the cells before and after it along
+        // the path, which is what makes corners come out right
+        for (index, &cell) in cells.iter().enumerate() {
+            let mut dirs = 0;
+            if let Some(&prev) = index.checked_sub(1).map(|i| &cells[i]) {
+                dirs |= step_dir(cell, prev);
+            }
+            if let Some(&next) = cells.get(index + 1) {
+                dirs |= step_dir(cell, next);
+            }
+            add_box_dirs(self, cell.row, cell.column, dirs, pen);
+        }
+
+        let n = cells.len();
+        let start_dir = step_dir(cells[1], cells[0]);
+        let end_dir = step_dir(cells[n - 2], cells[n - 1]);
+        draw_markers(self, pen, cells[0], start_dir, cells[n - 1], end_dir);
+    }
+
+    /// Finds a path from `from` to `to` that goes around the given
+    /// obstacles (usually the boxes already drawn), preferring short
+    /// paths with few bends, and draws it with `draw_polyline_with`.
+    /// The path may use a couple of rows below the lowest obstacle.
+    ///
+    /// If `avoid_lines` is true, the path also tries to keep clear of
+    /// lines that are already drawn, crossing them only where there is
+    /// no reasonable way around.
+    ///
+    /// Returns the points where the path starts, bends and ends, or
+    /// `None` (drawing nothing) if there is no way through.
+    fn draw_route_with<P: Into<Pen>>(
+        &mut self,
+        obstacles: &[Rect],
+        from: Point,
+        to: Point,
+        pen: P,
+        avoid_lines: bool,
+    ) -> Option<Vec<Point>> {
+        let last_row = obstacles
+            .iter()
+            .map(|r| r.end_row())
+            .chain(Some(from.row))
+            .chain(Some(to.row))
+            .max()
+            .unwrap_or(0);
+        let bounds = Rect::new(0, 0, last_row + 2, self.columns());
+        let points = route::find_route(bounds, obstacles, from, to, |point| {
+            avoid_lines && !self.read_lines(point.row, point.column).is_empty()
+        })?;
+        self.draw_polyline_with(&points, pen);
+        Some(points)
+    }
+
+    /// Like `draw_route_with`, drawing a light line and ignoring any
+    /// lines already drawn.
+    fn draw_route(&mut self, obstacles: &[Rect], from: Point, to: Point) -> Option<Vec<Point>> {
+        self.draw_route_with(obstacles, from, to, LineStyle::Light, false)
+    }
+
+    /// Erases a line previously drawn with `draw_vertical_line`: the
+    /// vertical segments are removed from each cell, so junctions turn
+    /// back into the simpler character (e.g., `┼` becomes `─`).
+    fn erase_vertical_line(&mut self, rows: Range<usize>, column: usize) {
+        for (r, dirs) in segment_dirs(rows, UP, DOWN) {
+            remove_box_dirs(self, r, column, dirs);
+        }
+    }
+
+    /// Erases a line previously drawn with `draw_horizontal_line`; see
+    /// `erase_vertical_line`.
+    fn erase_horizontal_line(&mut self, row: usize, columns: Range<usize>) {
+        for (c, dirs) in segment_dirs(columns, LEFT, RIGHT) {
+            remove_box_dirs(self, row, c, dirs);
+        }
+    }
+
+    /// Draws the border of a rectangle. Where the border meets lines
+    /// that are already there, the junctions merge as usual. Any
+    /// markers on the pen are ignored.
+    fn draw_rect<P: Into<Pen>>(&mut self, rect: Rect, pen: P) {
+        let pen = pen.into().with_markers(Marker::None, Marker::None);
+        if rect.is_empty() {
+            return;
+        }
+        let (last_row, last_column) = (rect.end_row() - 1, rect.end_column() - 1);
+        self.draw_horizontal_line_with(rect.row, rect.columns(), pen);
+        self.draw_horizontal_line_with(last_row, rect.columns(), pen);
+        self.draw_vertical_line_with(rect.rows(), rect.column, pen);
+        self.draw_vertical_line_with(rect.rows(), last_column, pen);
+    }
+
+    /// Draws a light rectangle with a title set into its top edge, and
+    /// returns a view whose upper-left corner is the first cell inside
+    /// the rectangle. Titles that do not fit are cut short.
+    fn draw_frame<'c>(
+        &'c mut self,
+        rect: Rect,
+        title: &str,
+        title_alignment: Alignment,
+    ) -> ShiftedView<'c, Self> {
+        self.draw_rect(rect, LineStyle::Light);
+
+        // leave the corners, and a space either side of the title
+        let room = rect.width.saturating_sub(4);
+        let title = self.sanitize().apply(title, rect.column + 2);
+        let title = match text::truncate(&title, room, 0, Truncate::End) {
+            Some((head, _)) => head,
+            None => &title,
+        };
+        let width = text::str_width(title);
+        if width > 0 {
+            let offset = title_alignment.offset(width, room);
+            let column = rect.column + 1 + offset;
+            // spaces let lines show through, so cut the border first
+            for c in column..column + width + 2 {
+                remove_box_dirs(self, rect.row, c, LEFT | RIGHT);
+            }
+            let title = format!(" {} ", title);
+            write_graphemes(self, rect.row, column, &title, Style::new());
+        }
+
+        self.shift(rect.row + 1, rect.column + 1)
     }
 
     /// Writes characters in the given style at the given position.
-    pub fn write_chars<I>(&mut self, row: usize, column: usize, chars: I, style: Style)
+    /// The characters are grouped into grapheme clusters, which get a
+    /// cell each, so combining marks stay with the letter before them.
+    /// Each cluster advances by its display width, so wide (e.g., CJK)
+    /// characters take up two columns. Control characters, tabs and
+    /// newlines are handled as the view's `sanitize` policy says.
+    fn write_chars<I>(&mut self, row: usize, column: usize, chars: I, style: Style)
+    where
+        I: Iterator<Item = char>,
+    {
+        let text: String = chars.collect();
+        let text = self.sanitize().apply(&text, column);
+        write_graphemes(self, row, column, &text, style);
+    }
+
+    /// Writes `text` as a terminal would: each tab advances to the next
+    /// tab stop (as set by the view's `sanitize` policy, or every 8
+    /// columns if the policy keeps tabs, counting from the view's left
+    /// edge), and each newline moves down a row and back to `column`.
+    /// Returns the position just after the last character written, so
+    /// that another write can pick up there.
+    fn write_text(&mut self, row: usize, column: usize, text: &str, style: Style) -> Point {
+        let (runs, cursor) = text_runs(self, row, column, text, style);
+        write_runs(self, &runs);
+        cursor
+    }
+
+    /// Writes characters running down the canvas from the given
+    /// position, one grapheme cluster per row (rows are added as
+    /// needed, as usual). Tab stops run across the canvas, not down
+    /// it, so a tab (unless the `sanitize` policy keeps it) becomes a
+    /// single blank cell.
+    fn write_chars_vertical<I>(&mut self, row: usize, column: usize, chars: I, style: Style)
     where
         I: Iterator<Item = char>,
     {
-        for (i, ch) in chars.enumerate() {
-            self.write_char(row, column + i, ch, style);
+        let text: String = chars.collect();
+        let runs = vertical_runs(self, row, column, &text, style);
+        write_runs(self, &runs);
+    }
+
+    /// Writes `text` in large letters using the given font, with its
+    /// upper-left corner at the given position. Returns the number of
+    /// rows and columns the banner takes up.
+    fn write_banner(
+        &mut self,
+        row: usize,
+        column: usize,
+        text: &str,
+        font: &Font,
+        style: Style,
+    ) -> (usize, usize) {
+        let runs = banner_runs(self, row, column, text, font, style);
+        write_runs(self, &runs);
+        runs_extent(&runs)
+    }
+
+    /// Word-wraps `text` to the width of `rect` and writes it there,
+    /// one line per row, aligned as requested. `wrap` decides what
+    /// happens to text that does not fit in the rectangle. Returns the
+    /// number of rows written.
+    fn write_text_block(
+        &mut self,
+        rect: Rect,
+        text: &str,
+        alignment: Alignment,
+        wrap: Wrap,
+        style: Style,
+    ) -> usize {
+        let runs = text_block_runs(self, rect, text, alignment, wrap, style);
+        write_runs(self, &runs);
+        runs.len()
+    }
+
+    /// Writes characters like `write_chars`, but in no more than
+    /// `max_width` columns. Text that is too wide is cut short at the
+    /// end, start or middle, and an ellipsis (`…`, or `...` when
+    /// rendering in ASCII) written in `ellipsis_style` marks the gap.
+    /// Returns the number of columns written.
+    #[allow(clippy::too_many_arguments)]
+    fn write_chars_fit<I>(
+        &mut self,
+        row: usize,
+        column: usize,
+        chars: I,
+        max_width: usize,
+        truncate: Truncate,
+        style: Style,
+        ellipsis_style: Style,
+    ) -> usize
+    where
+        I: Iterator<Item = char>,
+    {
+        let text: String = chars.collect();
+        let styles = (style, ellipsis_style);
+        let runs = fit_runs(self, row, column, &text, max_width, truncate, styles);
+        write_runs(self, &runs);
+        runs_width(&runs)
+    }
+
+    /// Like `read_char`, but returns an error rather than panicking if
+    /// the cell is out of bounds.
+    fn try_read_char(&mut self, row: usize, column: usize) -> Result<char, CanvasError> {
+        self.check_bounds(row, column)?;
+        Ok(self.read_char(row, column))
+    }
+
+    /// Like `write_char`, but returns an error rather than panicking if
+    /// the cell is out of bounds.
+    fn try_write_char(
+        &mut self,
+        row: usize,
+        column: usize,
+        ch: char,
+        style: Style,
+    ) -> Result<(), CanvasError> {
+        self.check_bounds(row, column)?;
+        self.write_char(row, column, ch, style);
+        Ok(())
+    }
+
+    /// Like `write_chars`, but returns an error (having written
+    /// nothing) if the text would run out of bounds.
+    fn try_write_chars<I>(
+        &mut self,
+        row: usize,
+        column: usize,
+        chars: I,
+        style: Style,
+    ) -> Result<(), CanvasError>
+    where
+        I: Iterator<Item = char>,
+    {
+        let text: String = chars.collect();
+        let runs = [Run {
+            row,
+            column,
+            text: self.sanitize().apply(&text, column),
+            style,
+        }];
+        check_runs(self, &runs)?;
+        write_runs(self, &runs);
+        Ok(())
+    }
+
+    /// Like `write_text`, but returns an error (having written
+    /// nothing) if the text would run out of bounds.
+    fn try_write_text(
+        &mut self,
+        row: usize,
+        column: usize,
+        text: &str,
+        style: Style,
+    ) -> Result<Point, CanvasError> {
+        let (runs, cursor) = text_runs(self, row, column, text, style);
+        check_runs(self, &runs)?;
+        write_runs(self, &runs);
+        Ok(cursor)
+    }
+
+    /// Like `write_chars_vertical`, but returns an error (having
+    /// written nothing) if the text would run out of bounds.
+    fn try_write_chars_vertical<I>(
+        &mut self,
+        row: usize,
+        column: usize,
+        chars: I,
+        style: Style,
+    ) -> Result<(), CanvasError>
+    where
+        I: Iterator<Item = char>,
+    {
+        let text: String = chars.collect();
+        let runs = vertical_runs(self, row, column, &text, style);
+        check_runs(self, &runs)?;
+        write_runs(self, &runs);
+        Ok(())
+    }
+
+    /// Like `write_banner`, but returns an error (having written
+    /// nothing) if the banner would run out of bounds.
+    fn try_write_banner(
+        &mut self,
+        row: usize,
+        column: usize,
+        text: &str,
+        font: &Font,
+        style: Style,
+    ) -> Result<(usize, usize), CanvasError> {
+        let runs = banner_runs(self, row, column, text, font, style);
+        check_runs(self, &runs)?;
+        write_runs(self, &runs);
+        Ok(runs_extent(&runs))
+    }
+
+    /// Like `write_text_block`, but returns an error (having written
+    /// nothing) if the text would run out of bounds.
+    fn try_write_text_block(
+        &mut self,
+        rect: Rect,
+        text: &str,
+        alignment: Alignment,
+        wrap: Wrap,
+        style: Style,
+    ) -> Result<usize, CanvasError> {
+        let runs = text_block_runs(self, rect, text, alignment, wrap, style);
+        check_runs(self, &runs)?;
+        write_runs(self, &runs);
+        Ok(runs.len())
+    }
+
+    /// Like `write_chars_fit`, but returns an error (having written
+    /// nothing) if the text would run out of bounds.
+    #[allow(clippy::too_many_arguments)]
+    fn try_write_chars_fit<I>(
+        &mut self,
+        row: usize,
+        column: usize,
+        chars: I,
+        max_width: usize,
+        truncate: Truncate,
+        style: Style,
+        ellipsis_style: Style,
+    ) -> Result<usize, CanvasError>
+    where
+        I: Iterator<Item = char>,
+    {
+        let text: String = chars.collect();
+        let styles = (style, ellipsis_style);
+        let runs = fit_runs(self, row, column, &text, max_width, truncate, styles);
+        check_runs(self, &runs)?;
+        write_runs(self, &runs);
+        Ok(runs_width(&runs))
+    }
+
+    /// Like `draw_vertical_line_with`, but returns an error (having
+    /// drawn nothing) if the line would run out of bounds.
+    fn try_draw_vertical_line_with<P: Into<Pen>>(
+        &mut self,
+        rows: Range<usize>,
+        column: usize,
+        pen: P,
+    ) -> Result<(), CanvasError> {
+        if rows.start < rows.end {
+            self.check_bounds(rows.start, column)?;
+            self.check_bounds(rows.end - 1, column)?;
+        }
+        self.draw_vertical_line_with(rows, column, pen);
+        Ok(())
+    }
+
+    /// Like `draw_horizontal_line_with`, but returns an error (having
+    /// drawn nothing) if the line would run out of bounds.
+    fn try_draw_horizontal_line_with<P: Into<Pen>>(
+        &mut self,
+        row: usize,
+        columns: Range<usize>,
+        pen: P,
+    ) -> Result<(), CanvasError> {
+        if columns.start < columns.end {
+            self.check_bounds(row, columns.start)?;
+            self.check_bounds(row, columns.end - 1)?;
+        }
+        self.draw_horizontal_line_with(row, columns, pen);
+        Ok(())
+    }
+
+    /// Like `draw_box_char`, but returns an error (having drawn
+    /// nothing) if `ch` is not a box-drawing character, a diagonal, a
+    /// marker or a space, or if the cell is out of bounds.
+    fn try_draw_box_char(
+        &mut self,
+        row: usize,
+        column: usize,
+        ch: char,
+        style: Style,
+    ) -> Result<(), CanvasError> {
+        let lines = LineCell::try_from_char(ch)?;
+        self.check_bounds(row, column)?;
+        let lines = self.read_lines(row, column).merge(lines);
+        self.write_lines(row, column, lines, style);
+        Ok(())
+    }
+
+    /// Like `draw_line_with`, but returns an error (having drawn
+    /// nothing) if the line would run out of bounds.
+    fn try_draw_line_with<P: Into<Pen>>(
+        &mut self,
+        from: Point,
+        to: Point,
+        pen: P,
+    ) -> Result<(), CanvasError> {
+        self.check_bounds(from.row, from.column)?;
+        self.check_bounds(to.row, to.column)?;
+        self.draw_line_with(from, to, pen);
+        Ok(())
+    }
+
+    /// Like `draw_polyline_with`, but returns an error (having drawn
+    /// nothing) if a segment is not horizontal or vertical, or if the
+    /// polyline would run out of bounds.
+    fn try_draw_polyline_with<P: Into<Pen>>(
+        &mut self,
+        points: &[Point],
+        pen: P,
+    ) -> Result<(), CanvasError> {
+        for pair in points.windows(2) {
+            check_segment(pair[0], pair[1])?;
         }
+        for point in points {
+            self.check_bounds(point.row, point.column)?;
+        }
+        self.draw_polyline_with(points, pen);
+        Ok(())
+    }
+
+    /// Like `draw_rect`, but returns an error (having drawn nothing)
+    /// if the rectangle would run out of bounds.
+    fn try_draw_rect<P: Into<Pen>>(&mut self, rect: Rect, pen: P) -> Result<(), CanvasError> {
+        check_corners(self, rect)?;
+        self.draw_rect(rect, pen);
+        Ok(())
+    }
+
+    /// Like `draw_frame`, but returns an error (having drawn nothing)
+    /// if the frame would run out of bounds.
+    fn try_draw_frame<'c>(
+        &'c mut self,
+        rect: Rect,
+        title: &str,
+        title_alignment: Alignment,
+    ) -> Result<ShiftedView<'c, Self>, CanvasError> {
+        check_corners(self, rect)?;
+        Ok(self.draw_frame(rect, title, title_alignment))
     }
 
     /// Creates a new view onto the same canvas, but writing at an offset.
-    pub fn shift<'c>(&'c mut self, row: usize, column: usize) -> ShiftedView<'c> {
+    fn shift<'c>(&'c mut self, row: usize, column: usize) -> ShiftedView<'c, Self> {
         ShiftedView::new(self, row, column)
     }
 
+    /// Creates a new view onto the same canvas, writing at a signed
+    /// offset, so that (0, 0) in the new view may lie above or to the
+    /// left of this one. `negative` decides what happens to things
+    /// drawn there.
+    fn offset<'c>(
+        &'c mut self,
+        row: isize,
+        column: isize,
+        negative: Negative,
+    ) -> OffsetView<'c, Self> {
+        OffsetView::new(self, row, column, negative)
+    }
+
+    /// Creates a new view onto the part of the canvas inside `rect`,
+    /// with (0, 0) at its upper-left corner. Anything written outside
+    /// the rectangle is silently dropped.
+    fn clip<'c>(&'c mut self, rect: Rect) -> ClipView<'c, Self> {
+        ClipView::new(self, rect)
+    }
+
+    /// Creates a new view onto the part of the canvas inside `rect`,
+    /// flipped or turned by `transform`, with box-drawing characters,
+    /// arrows and slashes turned to match. Views that transpose or
+    /// turn a quarter turn are `rect.height` columns wide and
+    /// `rect.width` rows high. Anything written outside the rectangle
+    /// is silently dropped.
+    fn transform<'c>(&'c mut self, rect: Rect, transform: Transform) -> TransformView<'c, Self> {
+        TransformView::new(self, rect, transform)
+    }
+
     /// Creates a new view onto the same canvas, but applying a style
     /// to all the characters written.
-    pub fn styled<'c>(&'c mut self, style: Style) -> StyleView<'c> {
+    fn styled<'c>(&'c mut self, style: Style) -> StyleView<'c, Self> {
         StyleView::new(self, style)
     }
 }
 
+impl<T: AsciiView + ?Sized> AsciiViewExt for T {}
+
+fn add_box_dirs<V: AsciiView + ?Sized>(
+    view: &mut V,
+    row: usize,
+    column: usize,
+    dirs: u8,
+    pen: Pen,
+) {
+    let lines = view.read_lines(row, column).add(dirs, pen.line_style);
+    view.write_lines(row, column, lines, pen.style);
+}
+
+fn add_box_diagonal<V: AsciiView + ?Sized>(
+    view: &mut V,
+    row: usize,
+    column: usize,
+    diagonal: u8,
+    pen: Pen,
+) {
+    let lines = view
+        .read_lines(row, column)
+        .add_diagonal(diagonal, pen.line_style);
+    view.write_lines(row, column, lines, pen.style);
+}
+
+/// Draws the pen's markers at either end of a line; `start_dir` and
+/// `end_dir` are the directions the line would continue in past
+/// each end.
+fn draw_markers<V: AsciiView + ?Sized>(
+    view: &mut V,
+    pen: Pen,
+    start: Point,
+    start_dir: u8,
+    end: Point,
+    end_dir: u8,
+) {
+    for &(marker, point, dir) in &[(pen.start, start, start_dir), (pen.end, end, end_dir)] {
+        if marker != Marker::None {
+            let lines = view
+                .read_lines(point.row, point.column)
+                .add_marker(marker, dir);
+            view.write_lines(point.row, point.column, lines, pen.style);
+        }
+    }
+}
+
+/// Writes text that has already been sanitized, one grapheme cluster
+/// per cell, and returns the number of columns it took up.
+fn write_graphemes<V: AsciiView + ?Sized>(
+    view: &mut V,
+    row: usize,
+    column: usize,
+    text: &str,
+    style: Style,
+) -> usize {
+    let mut width = 0;
+    for grapheme in text.graphemes(true) {
+        let grapheme_width = text_width(grapheme);
+        if grapheme_width > 0 {
+            view.write_grapheme(row, column + width, grapheme, style);
+            width += grapheme_width;
+        }
+    }
+    width
+}
+
+/// A row's worth of text, already sanitized, waiting to be written.
+/// The text-writing methods lay their text out as runs first, so that
+/// their `try_` versions can check every run before writing any.
+struct Run {
+    row: usize,
+    column: usize,
+    text: String,
+    style: Style,
+}
+
+fn write_runs<V: AsciiView + ?Sized>(view: &mut V, runs: &[Run]) {
+    for run in runs {
+        write_graphemes(view, run.row, run.column, &run.text, run.style);
+    }
+}
+
+/// Checks the first and last cell of every run.
+fn check_runs<V: AsciiView + ?Sized>(view: &V, runs: &[Run]) -> Result<(), CanvasError> {
+    for run in runs {
+        let width = text::str_width(&run.text);
+        if width > 0 {
+            view.check_bounds(run.row, run.column)?;
+            view.check_bounds(run.row, run.column + width - 1)?;
+        }
+    }
+    Ok(())
+}
+
+/// The total width of the runs.
+fn runs_width(runs: &[Run]) -> usize {
+    runs.iter().map(|r| text::str_width(&r.text)).sum()
+}
+
+/// The number of runs, and the width of the widest.
+fn runs_extent(runs: &[Run]) -> (usize, usize) {
+    let width = runs.iter().map(|r| text::str_width(&r.text)).max();
+    (runs.len(), width.unwrap_or(0))
+}
+
+/// Lays out text for `write_text`, returning the runs along with the
+/// position just after the last one.
+fn text_runs<V: AsciiView + ?Sized>(
+    view: &V,
+    row: usize,
+    column: usize,
+    text: &str,
+    style: Style,
+) -> (Vec<Run>, Point) {
+    let sanitize = view.sanitize();
+    let sanitize = Sanitize {
+        tab_stop: Some(sanitize.tab_stop.unwrap_or(8)),
+        ..sanitize
+    };
+    let mut runs = vec![];
+    let mut cursor = Point::new(row, column);
+    for (index, line) in text.split('\n').enumerate() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if index > 0 {
+            cursor = Point::new(cursor.row + 1, column);
+        }
+        let text = sanitize.apply(line, cursor.column);
+        let width = text::str_width(&text);
+        runs.push(Run {
+            row: cursor.row,
+            column: cursor.column,
+            text,
+            style,
+        });
+        cursor.column += width;
+    }
+    (runs, cursor)
+}
+
+/// Lays out text for `write_chars_vertical`, one cluster per run.
+fn vertical_runs<V: AsciiView + ?Sized>(
+    view: &V,
+    row: usize,
+    column: usize,
+    text: &str,
+    style: Style,
+) -> Vec<Run> {
+    let sanitize = view.sanitize();
+    let sanitize = Sanitize {
+        tab_stop: sanitize.tab_stop.map(|_| 1),
+        ..sanitize
+    };
+    let text = sanitize.apply(text, 0);
+    let graphemes = text.graphemes(true).filter(|g| text_width(g) > 0);
+    graphemes
+        .enumerate()
+        .map(|(index, grapheme)| Run {
+            row: row + index,
+            column,
+            text: grapheme.to_string(),
+            style,
+        })
+        .collect()
+}
+
+fn banner_runs<V: AsciiView + ?Sized>(
+    view: &V,
+    row: usize,
+    column: usize,
+    text: &str,
+    font: &Font,
+    style: Style,
+) -> Vec<Run> {
+    let sanitize = view.sanitize();
+    let lines = font.render(text);
+    lines
+        .iter()
+        .enumerate()
+        .map(|(index, line)| Run {
+            row: row + index,
+            column,
+            text: sanitize.apply(line, column),
+            style,
+        })
+        .collect()
+}
+
+fn text_block_runs<V: AsciiView + ?Sized>(
+    view: &V,
+    rect: Rect,
+    text: &str,
+    alignment: Alignment,
+    wrap: Wrap,
+    style: Style,
+) -> Vec<Run> {
+    if rect.width == 0 {
+        return vec![];
+    }
+    let lines = text::wrap(text, rect.width);
+    let rows = match wrap {
+        Wrap::Clip | Wrap::Ellipsis => cmp::min(lines.len(), rect.height),
+        Wrap::Overflow => lines.len(),
+    };
+    let mut runs = vec![];
+    for (index, line) in lines.iter().take(rows).enumerate() {
+        let (offset, mut line_text) = line.layout(rect.width, alignment);
+        if wrap == Wrap::Ellipsis && index + 1 == rows && rows < lines.len() {
+            let ellipsis = view.charset().ellipsis();
+            line_text = text::with_ellipsis(&line_text, rect.width - offset, ellipsis);
+        }
+        let column = rect.column + offset;
+        runs.push(Run {
+            row: rect.row + index,
+            column,
+            text: view.sanitize().apply(&line_text, column),
+            style,
+        });
+    }
+    runs
+}
+
+/// Lays out text for `write_chars_fit`: the whole text, or the head,
+/// ellipsis and tail that are kept of it. `styles` are for the text
+/// and the ellipsis.
+fn fit_runs<V: AsciiView + ?Sized>(
+    view: &V,
+    row: usize,
+    column: usize,
+    text: &str,
+    max_width: usize,
+    truncate: Truncate,
+    (style, ellipsis_style): (Style, Style),
+) -> Vec<Run> {
+    let text = view.sanitize().apply(text, column);
+    let ellipsis = view.charset().ellipsis();
+    let ellipsis_width = text::str_width(ellipsis);
+    let (head, tail) = match text::truncate(&text, max_width, ellipsis_width, truncate) {
+        Some(parts) => parts,
+        None => {
+            let run = Run {
+                row,
+                column,
+                text,
+                style,
+            };
+            return vec![run];
+        }
+    };
+    if ellipsis_width > max_width {
+        return vec![];
+    }
+
+    let mut column = column;
+    let mut runs = vec![];
+    for &(part, part_style) in &[(head, style), (ellipsis, ellipsis_style), (tail, style)] {
+        runs.push(Run {
+            row,
+            column,
+            text: part.to_string(),
+            style: part_style,
+        });
+        column += text::str_width(part);
+    }
+    runs
+}
+
+/// Checks all four corners of `rect`, for drawing that stays inside
+/// it.
+fn check_corners<V: AsciiView + ?Sized>(view: &V, rect: Rect) -> Result<(), CanvasError> {
+    if rect.is_empty() {
+        return Ok(());
+    }
+    for &row in &[rect.row, rect.end_row() - 1] {
+        for &column in &[rect.column, rect.end_column() - 1] {
+            view.check_bounds(row, column)?;
+        }
+    }
+    Ok(())
+}
+
+/// How many of the first `columns` columns are in bounds, given that
+/// once a column is out of bounds, so is every column after it. This
+/// takes a binary search, since a canvas can be very wide.
+fn columns_in_bounds<F: Fn(usize) -> bool>(columns: usize, in_bounds: F) -> usize {
+    let (mut low, mut high) = (0, columns);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if in_bounds(mid) {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    low
+}
+
+fn remove_box_dirs<V: AsciiView + ?Sized>(view: &mut V, row: usize, column: usize, dirs: u8) {
+    let lines = view.read_lines(row, column).remove(dirs);
+    view.write_lines(row, column, lines, Style::new());
+}
+
+/// An `AsciiCanvas` keeps two layers: the text written with
+/// `write_char` and friends, and the lines drawn with
+/// `draw_vertical_line` and friends. Lines are tracked by direction
+/// rather than by character, so that where they intersect we can pick
+/// the right junction no matter what text was written there. When the
+/// canvas is rendered, text takes precedence: a cell shows its line
+/// only if no text (other than a space) was written into it.
 pub struct AsciiCanvas {
     columns: usize,
     rows: usize,
-    characters: Vec<char>,
+    text: Vec<Text>,
     styles: Vec<Style>,
+    lines: Vec<LineCell>,
+    line_styles: Vec<Style>,
+    max_columns: usize,
+    charset: Charset,
+    junction_rule: JunctionRule,
+    sanitize: Sanitize,
+}
+
+/// The text in a cell of an `AsciiCanvas`.
+#[derive(Clone)]
+enum Text {
+    /// Nothing (or only a space) has been written here, so any lines
+    /// show through.
+    Empty,
+    /// The second cell covered by a wide character, which is not
+    /// emitted separately.
+    WideContinuation,
+    Grapheme(Grapheme),
 }
 
-/// To use an `AsciiCanvas`, first create the canvas, then draw any
-/// lines, then write text labels. It is required to draw the lines
-/// first so that we can detect intersecting lines properly (we could
-/// track which characters belong to lines, I suppose).
 impl AsciiCanvas {
     /// Create a canvas of the given size. We will automatically add
-    /// rows as needed, but the columns are fixed at creation.
+    /// rows as needed, but the columns are fixed at creation unless
+    /// `set_max_columns` says otherwise.
     pub fn new(rows: usize, columns: usize) -> Self {
         AsciiCanvas {
-            rows: rows,
-            columns: columns,
-            characters: vec![' '; columns * rows],
+            rows,
+            columns,
+            text: vec![Text::Empty; columns * rows],
             styles: vec![Style::new(); columns * rows],
+            lines: vec![LineCell::new(); columns * rows],
+            line_styles: vec![Style::new(); columns * rows],
+            max_columns: columns,
+            charset: Charset::Unicode,
+            junction_rule: JunctionRule::LastWins,
+            sanitize: Sanitize::default(),
+        }
+    }
+
+    /// Selects the style given to cells where lines of different
+    /// styles meet.
+    pub fn set_junction_rule(&mut self, junction_rule: JunctionRule) {
+        self.junction_rule = junction_rule;
+    }
+
+    /// Selects the characters used when the canvas is converted into
+    /// strings or written to a terminal. Lines are always stored as
+    /// Unicode box-drawing characters (so junctions merge the same way
+    /// whatever the charset) and converted on the way out.
+    pub fn set_charset(&mut self, charset: Charset) {
+        self.charset = charset;
+    }
+
+    /// Selects how control characters, tabs and newlines in written
+    /// text are handled. `Sanitize::none()` writes them as they are.
+    pub fn set_sanitize(&mut self, sanitize: Sanitize) {
+        self.sanitize = sanitize;
+    }
+
+    /// Lets the canvas grow wider, up to `max_columns`, when something
+    /// is written past its right edge; pass `usize::MAX` for no limit.
+    /// Every row is widened to match. The canvas never shrinks, so a
+    /// maximum below its current width just stops it growing.
+    pub fn set_max_columns(&mut self, max_columns: usize) {
+        self.max_columns = cmp::max(max_columns, self.columns);
+    }
+
+    fn grow_columns_if_needed(&mut self, new_columns: usize) {
+        if new_columns > self.columns {
+            assert!(new_columns <= self.max_columns);
+            self.relayout(0, 0, new_columns);
         }
     }
 
-    fn grow_rows_if_needed(&mut self, new_rows: usize) {
-        if new_rows >= self.rows {
-            let new_chars = (new_rows - self.rows) * self.columns;
-            self.characters.extend((0..new_chars).map(|_| ' '));
-            self.styles.extend((0..new_chars).map(|_| Style::new()));
-            self.rows = new_rows;
+    /// Moves every cell `top` rows down and `left` columns right in a
+    /// canvas that is now `new_columns` wide, leaving the new cells
+    /// empty. (The caller accounts for the new rows.)
+    fn relayout(&mut self, top: usize, left: usize, new_columns: usize) {
+        let (rows, columns) = (self.rows, self.columns);
+        let shape = (rows, columns, top, left, new_columns);
+        self.text = relayout(&self.text, shape, Text::Empty);
+        self.styles = relayout(&self.styles, shape, Style::new());
+        self.lines = relayout(&self.lines, shape, LineCell::new());
+        self.line_styles = relayout(&self.line_styles, shape, Style::new());
+        self.columns = new_columns;
+    }
+
+    fn grow_rows_if_needed(&mut self, new_rows: usize) {
+        if new_rows >= self.rows {
+            let new_chars = (new_rows - self.rows) * self.columns;
+            self.text.extend((0..new_chars).map(|_| Text::Empty));
+            self.styles.extend((0..new_chars).map(|_| Style::new()));
+            self.lines.extend((0..new_chars).map(|_| LineCell::new()));
+            self.line_styles
+                .extend((0..new_chars).map(|_| Style::new()));
+            self.rows = new_rows;
+        }
+    }
+
+    fn index(&mut self, r: usize, c: usize) -> usize {
+        self.grow_columns_if_needed(c + 1);
+        self.grow_rows_if_needed(r + 1);
+        self.in_range_index(r, c)
+    }
+
+    /// The index of a cell the canvas already has room for, without
+    /// growing it.
+    fn existing_index(&self, r: usize, c: usize) -> Option<usize> {
+        if r < self.rows && c < self.columns {
+            Some(r * self.columns + c)
+        } else {
+            None
+        }
+    }
+
+    fn in_range_index(&self, r: usize, c: usize) -> usize {
+        assert!(r < self.rows);
+        assert!(c <= self.columns);
+        r * self.columns + c
+    }
+
+    /// The grapheme and style shown at the given index, once text and
+    /// lines are combined.
+    fn cell(&self, index: usize) -> (Grapheme, Style) {
+        match &self.text[index] {
+            Text::Empty => (
+                Grapheme::from(self.lines[index].to_char()),
+                self.line_styles[index],
+            ),
+            Text::WideContinuation => (Grapheme::from(' '), self.styles[index]),
+            Text::Grapheme(grapheme) => (grapheme.clone(), self.styles[index]),
+        }
+    }
+
+    /// Before the cell at `index` is overwritten, blanks out whatever
+    /// would be left of a wide character that covers it.
+    fn clear_wide_char(&mut self, index: usize, column: usize) {
+        let is_continuation = |text: &Text| matches!(text, Text::WideContinuation);
+        let blank = Text::Empty;
+        if is_continuation(&self.text[index]) {
+            self.text[index - 1] = blank;
+        } else if column + 1 < self.columns && is_continuation(&self.text[index + 1]) {
+            self.text[index + 1] = blank;
+        }
+    }
+
+    /// Removes all the lines, leaving the text alone.
+    pub fn clear_lines(&mut self) {
+        for lines in &mut self.lines {
+            *lines = LineCell::new();
+        }
+    }
+
+    fn start_index(&self, r: usize) -> usize {
+        self.in_range_index(r, 0)
+    }
+
+    fn end_index(&self, r: usize) -> usize {
+        self.in_range_index(r, self.columns)
+    }
+
+    pub fn write_to<T: Terminal + ?Sized>(&self, term: &mut T) -> term::Result<()> {
+        for row in self.to_strings() {
+            row.write_to(term)?;
+            writeln!(term)?;
+        }
+        Ok(())
+    }
+
+    pub fn to_strings(&self) -> Vec<Row> {
+        (0..self.rows)
+            .map(|row| {
+                let start = self.start_index(row);
+                let end = self.end_index(row);
+                let cells: Vec<(Grapheme, Style)> = (start..end)
+                    .filter(|&index| !matches!(self.text[index], Text::WideContinuation))
+                    .map(|index| {
+                        let (grapheme, style) = self.cell(index);
+                        (self.charset.convert_grapheme(grapheme), style)
+                    })
+                    .collect();
+                Row::from_graphemes(cells.iter().map(|(g, style)| (g.as_str(), *style)))
+            })
+            .collect()
+    }
+}
+
+impl AsciiView for AsciiCanvas {
+    fn columns(&self) -> usize {
+        self.columns
+    }
+
+    fn charset(&self) -> Charset {
+        self.charset
+    }
+
+    fn sanitize(&self) -> Sanitize {
+        self.sanitize
+    }
+
+    /// Cells past the current width are fine, so long as the canvas
+    /// can grow to reach them.
+    fn check_bounds(&self, row: usize, column: usize) -> Result<(), CanvasError> {
+        if column < self.max_columns {
+            Ok(())
+        } else {
+            Err(CanvasError::OutOfBounds {
+                row,
+                column,
+                columns: self.max_columns,
+            })
+        }
+    }
+
+    /// Fails if the new columns would take the canvas past its
+    /// maximum width.
+    fn grow_up_left(&mut self, rows: usize, columns: usize) -> bool {
+        let new_columns = self.columns + columns;
+        if new_columns > self.max_columns {
+            return false;
+        }
+        self.relayout(rows, columns, new_columns);
+        self.rows += rows;
+        true
+    }
+
+    fn read_char(&mut self, row: usize, column: usize) -> char {
+        self.read_grapheme(row, column).first_char()
+    }
+
+    fn write_char(&mut self, row: usize, column: usize, ch: char, style: Style) {
+        self.write_grapheme(row, column, ch.encode_utf8(&mut [0; 4]), style)
+    }
+
+    /// Cells the canvas has not grown to yet are blank; reading them
+    /// does not grow it.
+    fn read_grapheme(&mut self, row: usize, column: usize) -> Grapheme {
+        assert!(column < self.max_columns);
+        match self.existing_index(row, column) {
+            Some(index) => self.cell(index).0,
+            None => Grapheme::from(' '),
+        }
+    }
+
+    fn read_style(&mut self, row: usize, column: usize) -> Style {
+        assert!(column < self.max_columns);
+        match self.existing_index(row, column) {
+            Some(index) => self.cell(index).1,
+            None => Style::new(),
+        }
+    }
+
+    /// Wide characters also claim the cell to their right; one that
+    /// would hang over the right edge is replaced with a space.
+    fn write_grapheme(&mut self, row: usize, column: usize, grapheme: &str, style: Style) {
+        assert!(column < self.max_columns);
+        let grapheme = self
+            .sanitize
+            .control
+            .sanitize_grapheme(grapheme)
+            .unwrap_or(grapheme);
+        let mut grapheme = Grapheme::new(grapheme);
+        let wide = text_width(&grapheme) == 2;
+        if wide && column + 1 < self.max_columns {
+            self.grow_columns_if_needed(column + 2);
+        }
+        let index = self.index(row, column);
+        self.clear_wide_char(index, column);
+        if wide {
+            if column + 1 < self.columns {
+                self.clear_wide_char(index + 1, column + 1);
+                self.text[index + 1] = Text::WideContinuation;
+                self.styles[index + 1] = style;
+            } else {
+                grapheme = Grapheme::from(' ');
+            }
+        }
+        self.text[index] = if &*grapheme == " " {
+            Text::Empty
+        } else {
+            Text::Grapheme(grapheme)
+        };
+        self.styles[index] = style;
+    }
+
+    /// Cells the canvas has not grown to yet have no lines; reading
+    /// them does not grow it.
+    fn read_lines(&mut self, row: usize, column: usize) -> LineCell {
+        assert!(column < self.max_columns);
+        match self.existing_index(row, column) {
+            Some(index) => self.lines[index],
+            None => LineCell::new(),
+        }
+    }
+
+    fn write_lines(&mut self, row: usize, column: usize, lines: LineCell, style: Style) {
+        assert!(column < self.max_columns);
+        let index = self.index(row, column);
+        let old_lines = self.lines[index];
+        if old_lines.is_empty() {
+            self.line_styles[index] = style;
+        } else if !old_lines.contains(lines) && self.line_styles[index] != style {
+            // a line of another style meets the ones already here (if
+            // nothing new was drawn, the lines keep their style)
+            let old_style = self.line_styles[index];
+            self.line_styles[index] = self.junction_rule.resolve(old_style, style);
+        }
+        self.lines[index] = lines;
+    }
+}
+
+/// Copies the cells of a canvas `rows` high and `columns` wide into
+/// one `new_columns` wide, `top` rows down and `left` columns right,
+/// filling the new cells with `fill`.
+fn relayout<T: Clone>(
+    cells: &[T],
+    (rows, columns, top, left, new_columns): (usize, usize, usize, usize, usize),
+    fill: T,
+) -> Vec<T> {
+    let mut result = vec![fill; (rows + top) * new_columns];
+    for row in 0..rows {
+        let start = (row + top) * new_columns + left;
+        result[start..start + columns].clone_from_slice(&cells[row * columns..(row + 1) * columns]);
+    }
+    result
+}
+
+/// The directions of a line running along `range`: each cell connects
+/// to its neighbours, except that the first cell has nothing before
+/// it and the last has nothing after it.
+fn segment_dirs(
+    range: Range<usize>,
+    backward: u8,
+    forward: u8,
+) -> impl Iterator<Item = (usize, u8)> {
+    let len = range.len();
+    range.enumerate().map(move |(index, i)| {
+        let dirs = if index == 0 {
+            forward
+        } else if index == len - 1 {
+            backward
+        } else {
+            backward | forward
+        };
+        (i, dirs)
+    })
+}
+
+/// The direction (`UP`, `DOWN`, `LEFT` or `RIGHT`) of a step between
+/// neighbouring points; diagonal steps count as horizontal.
+fn step_dir(from: Point, to: Point) -> u8 {
+    if to.column > from.column {
+        RIGHT
+    } else if to.column < from.column {
+        LEFT
+    } else if to.row > from.row {
+        DOWN
+    } else {
+        UP
+    }
+}
+
+/// Every cell along a polyline, in order, with each one a horizontal
+/// or vertical neighbour of the one before.
+fn polyline_cells(points: &[Point]) -> Vec<Point> {
+    let mut cells: Vec<Point> = points.iter().cloned().take(1).collect();
+    for pair in points.windows(2) {
+        let (from, to) = (pair[0], pair[1]);
+        if let Err(error) = check_segment(from, to) {
+            panic!("{}", error);
+        }
+        let mut cell = from;
+        while cell != to {
+            match (cell.row.cmp(&to.row), cell.column.cmp(&to.column)) {
+                (cmp::Ordering::Less, _) => cell.row += 1,
+                (cmp::Ordering::Greater, _) => cell.row -= 1,
+                (_, cmp::Ordering::Less) => cell.column += 1,
+                (_, _) => cell.column -= 1,
+            }
+            cells.push(cell);
         }
     }
+    cells
+}
 
-    fn index(&mut self, r: usize, c: usize) -> usize {
-        self.grow_rows_if_needed(r + 1);
-        self.in_range_index(r, c)
+/// Checks that a polyline segment is horizontal or vertical.
+fn check_segment(from: Point, to: Point) -> Result<(), CanvasError> {
+    if from.row == to.row || from.column == to.column {
+        Ok(())
+    } else {
+        Err(CanvasError::InvalidGeometry { from, to })
     }
+}
 
-    fn in_range_index(&self, r: usize, c: usize) -> usize {
-        assert!(r < self.rows);
-        assert!(c <= self.columns);
-        r * self.columns + c
+/// The points along a line from `from` to `to`, as chosen by
+/// Bresenham's algorithm: each point is one of the eight neighbours of
+/// the one before.
+fn bresenham(from: Point, to: Point) -> Vec<Point> {
+    let (row1, column1) = (to.row as isize, to.column as isize);
+    let (mut row, mut column) = (from.row as isize, from.column as isize);
+    let delta_rows = (row1 - row).abs();
+    let delta_columns = (column1 - column).abs();
+    let step_row = (row1 - row).signum();
+    let step_column = (column1 - column).signum();
+    let mut error = delta_columns - delta_rows;
+    let mut points = vec![];
+    loop {
+        points.push(Point::new(row as usize, column as usize));
+        if row == row1 && column == column1 {
+            return points;
+        }
+        let error2 = 2 * error;
+        if error2 > -delta_rows {
+            error -= delta_rows;
+            column += step_column;
+        }
+        if error2 < delta_columns {
+            error += delta_columns;
+            row += step_row;
+        }
     }
+}
 
-    fn start_index(&self, r: usize) -> usize {
-        self.in_range_index(r, 0)
-    }
+/// A position on the canvas (or within a view).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Point {
+    pub row: usize,
+    pub column: usize,
+}
 
-    fn end_index(&self, r: usize) -> usize {
-        self.in_range_index(r, self.columns)
+impl Point {
+    pub fn new(row: usize, column: usize) -> Point {
+        Point { row, column }
     }
+}
 
-    pub fn write_to<T: Terminal + ?Sized>(&self, term: &mut T) -> term::Result<()> {
-        for row in self.to_strings() {
-            row.write_to(term)?;
-            writeln!(term, "")?;
+/// A rectangle on the canvas (or within a view), given by its
+/// upper-left corner and its size.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Rect {
+    pub row: usize,
+    pub column: usize,
+    pub height: usize,
+    pub width: usize,
+}
+
+impl Rect {
+    pub fn new(row: usize, column: usize, height: usize, width: usize) -> Rect {
+        Rect {
+            row,
+            column,
+            height,
+            width,
         }
-        Ok(())
     }
 
-    pub fn to_strings(&self) -> Vec<Row> {
-        (0..self.rows)
-            .map(|row| {
-                let start = self.start_index(row);
-                let end = self.end_index(row);
-                let chars = &self.characters[start..end];
-                let styles = &self.styles[start..end];
-                Row::new(chars, styles)
-            })
-            .collect()
+    pub fn is_empty(&self) -> bool {
+        self.height == 0 || self.width == 0
     }
-}
 
-impl AsciiView for AsciiCanvas {
-    fn columns(&self) -> usize {
-        self.columns
+    /// The row just below the rectangle.
+    pub fn end_row(&self) -> usize {
+        self.row + self.height
     }
 
-    fn read_char(&mut self, row: usize, column: usize) -> char {
-        assert!(column < self.columns);
-        let index = self.index(row, column);
-        self.characters[index]
+    /// The column just right of the rectangle.
+    pub fn end_column(&self) -> usize {
+        self.column + self.width
     }
 
-    fn write_char(&mut self, row: usize, column: usize, ch: char, style: Style) {
-        assert!(column < self.columns);
-        let index = self.index(row, column);
-        self.characters[index] = ch;
-        self.styles[index] = style;
+    pub fn rows(&self) -> Range<usize> {
+        self.row..self.end_row()
     }
-}
 
-#[derive(Copy, Clone)]
-struct Point {
-    row: usize,
-    column: usize,
+    pub fn columns(&self) -> Range<usize> {
+        self.column..self.end_column()
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        self.rows().contains(&point.row) && self.columns().contains(&point.column)
+    }
 }
 
 /// Gives a view onto an AsciiCanvas that has a fixed upper-left
@@ -195,9 +1555,9 @@ struct Point {
 /// Shifted views also track the extent of the characters which are
 /// written through them; the `close()` method can be used to read
 /// that out when you are finished.
-pub struct ShiftedView<'canvas> {
+pub struct ShiftedView<'canvas, V: ?Sized = dyn AsciiView + 'canvas> {
     // either the base canvas or another view
-    base: &'canvas mut dyn AsciiView,
+    base: &'canvas mut V,
 
     // fixed at creation: the content is always allowed to grow down,
     // but cannot grow right more than `num_columns`
@@ -207,15 +1567,12 @@ pub struct ShiftedView<'canvas> {
     lower_right: Point,
 }
 
-impl<'canvas> ShiftedView<'canvas> {
-    fn new(base: &'canvas mut dyn AsciiView, row: usize, column: usize) -> Self {
-        let upper_left = Point {
-            row: row,
-            column: column,
-        };
+impl<'canvas, V: AsciiView + ?Sized> ShiftedView<'canvas, V> {
+    fn new(base: &'canvas mut V, row: usize, column: usize) -> Self {
+        let upper_left = Point { row, column };
         ShiftedView {
-            base: base,
-            upper_left: upper_left,
+            base,
+            upper_left,
             lower_right: upper_left,
         }
     }
@@ -236,9 +1593,41 @@ impl<'canvas> ShiftedView<'canvas> {
     }
 }
 
-impl<'canvas> AsciiView for ShiftedView<'canvas> {
+impl<'canvas, V: AsciiView + ?Sized> AsciiView for ShiftedView<'canvas, V> {
     fn columns(&self) -> usize {
-        self.base.columns() - self.upper_left.column
+        self.base.columns().saturating_sub(self.upper_left.column)
+    }
+
+    fn charset(&self) -> Charset {
+        self.base.charset()
+    }
+
+    fn sanitize(&self) -> Sanitize {
+        self.base.sanitize()
+    }
+
+    fn check_bounds(&self, row: usize, column: usize) -> Result<(), CanvasError> {
+        let row = self.upper_left.row + row;
+        let column = self.upper_left.column + column;
+        self.base.check_bounds(row, column)
+    }
+
+    /// Makes room out of the part of the view beneath that lies above
+    /// and to the left of this one, growing the view beneath only for
+    /// the rest.
+    fn grow_up_left(&mut self, rows: usize, columns: usize) -> bool {
+        let extra_rows = rows.saturating_sub(self.upper_left.row);
+        let extra_columns = columns.saturating_sub(self.upper_left.column);
+        if (extra_rows > 0 || extra_columns > 0)
+            && !self.base.grow_up_left(extra_rows, extra_columns)
+        {
+            return false;
+        }
+        self.upper_left.row = self.upper_left.row + extra_rows - rows;
+        self.upper_left.column = self.upper_left.column + extra_columns - columns;
+        self.lower_right.row += extra_rows;
+        self.lower_right.column += extra_columns;
+        true
     }
 
     fn read_char(&mut self, row: usize, column: usize) -> char {
@@ -250,33 +1639,503 @@ impl<'canvas> AsciiView for ShiftedView<'canvas> {
     fn write_char(&mut self, row: usize, column: usize, ch: char, style: Style) {
         let row = self.upper_left.row + row;
         let column = self.upper_left.column + column;
-        self.track_max(row, column);
+        self.track_max(
+            row,
+            column + text_width(ch.encode_utf8(&mut [0; 4])).saturating_sub(1),
+        );
         self.base.write_char(row, column, ch, style)
     }
+
+    fn read_grapheme(&mut self, row: usize, column: usize) -> Grapheme {
+        let row = self.upper_left.row + row;
+        let column = self.upper_left.column + column;
+        self.base.read_grapheme(row, column)
+    }
+
+    fn read_style(&mut self, row: usize, column: usize) -> Style {
+        let row = self.upper_left.row + row;
+        let column = self.upper_left.column + column;
+        self.base.read_style(row, column)
+    }
+
+    fn write_grapheme(&mut self, row: usize, column: usize, grapheme: &str, style: Style) {
+        let row = self.upper_left.row + row;
+        let column = self.upper_left.column + column;
+        self.track_max(row, column + text_width(grapheme).saturating_sub(1));
+        self.base.write_grapheme(row, column, grapheme, style)
+    }
+
+    fn read_lines(&mut self, row: usize, column: usize) -> LineCell {
+        let row = self.upper_left.row + row;
+        let column = self.upper_left.column + column;
+        self.base.read_lines(row, column)
+    }
+
+    fn write_lines(&mut self, row: usize, column: usize, lines: LineCell, style: Style) {
+        let row = self.upper_left.row + row;
+        let column = self.upper_left.column + column;
+        self.track_max(row, column);
+        self.base.write_lines(row, column, lines, style)
+    }
+}
+
+/// What an `OffsetView` does with cells that fall above or to the left
+/// of the view beneath it.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Negative {
+    /// Drops anything written there; reading there gives spaces.
+    #[default]
+    Clip,
+    /// Grows the view beneath up and left to make room, if it can (an
+    /// `AsciiCanvas` can, up to its maximum width, and a `ShiftedView`
+    /// can within the margin it was shifted by, or beyond it if what it
+    /// sits on can); otherwise clips.
+    Grow,
+}
+
+/// Gives a view onto an AsciiCanvas whose origin may be above or to
+/// the left of the view beneath it, so that content laid out around
+/// a centre point can be drawn without normalizing every coordinate.
+/// You can get one of these by calling the `offset()` method on any
+/// ASCII view.
+pub struct OffsetView<'canvas, V: ?Sized = dyn AsciiView + 'canvas> {
+    base: &'canvas mut V,
+
+    // where (0, 0) in this view lies in the base view
+    row: isize,
+    column: isize,
+
+    negative: Negative,
+
+    // how far the base view's content has moved to make room
+    origin_shift: Point,
+}
+
+impl<'canvas, V: AsciiView + ?Sized> OffsetView<'canvas, V> {
+    fn new(base: &'canvas mut V, row: isize, column: isize, negative: Negative) -> Self {
+        OffsetView {
+            base,
+            row,
+            column,
+            negative,
+            origin_shift: Point::new(0, 0),
+        }
+    }
+
+    /// How many rows and columns were added above and to the left of
+    /// the base view to make room for what was drawn. Everything that
+    /// was in the base view has moved down and right by this much.
+    pub fn origin_shift(&self) -> Point {
+        self.origin_shift
+    }
+
+    /// Where the given cell lies in the base view, or `None` if it
+    /// lies outside. When writing, the base view is grown to make room
+    /// if need be.
+    fn base_position(
+        &mut self,
+        row: usize,
+        column: usize,
+        writing: bool,
+    ) -> Option<(usize, usize)> {
+        let mut row = self.row + row as isize;
+        let mut column = self.column + column as isize;
+        if (row < 0 || column < 0) && writing && self.negative == Negative::Grow {
+            let up = cmp::max(-row, 0);
+            let left = cmp::max(-column, 0);
+            if self.base.grow_up_left(up as usize, left as usize) {
+                self.row += up;
+                self.column += left;
+                self.origin_shift.row += up as usize;
+                self.origin_shift.column += left as usize;
+                row += up;
+                column += left;
+            }
+        }
+        if row < 0 || column < 0 {
+            None
+        } else {
+            Some((row as usize, column as usize))
+        }
+    }
+}
+
+impl<'canvas, V: AsciiView + ?Sized> AsciiView for OffsetView<'canvas, V> {
+    fn columns(&self) -> usize {
+        cmp::max(self.base.columns() as isize - self.column, 0) as usize
+    }
+
+    fn charset(&self) -> Charset {
+        self.base.charset()
+    }
+
+    fn sanitize(&self) -> Sanitize {
+        self.base.sanitize()
+    }
+
+    /// Cells above or to the left of the base view are always fine:
+    /// they are either clipped or made room for.
+    fn check_bounds(&self, row: usize, column: usize) -> Result<(), CanvasError> {
+        let row = self.row + row as isize;
+        let column = self.column + column as isize;
+        if row < 0 || column < 0 {
+            Ok(())
+        } else {
+            self.base.check_bounds(row as usize, column as usize)
+        }
+    }
+
+    fn read_char(&mut self, row: usize, column: usize) -> char {
+        match self.base_position(row, column, false) {
+            Some((row, column)) => self.base.read_char(row, column),
+            None => ' ',
+        }
+    }
+
+    fn write_char(&mut self, row: usize, column: usize, ch: char, style: Style) {
+        if let Some((row, column)) = self.base_position(row, column, true) {
+            self.base.write_char(row, column, ch, style)
+        }
+    }
+
+    fn read_grapheme(&mut self, row: usize, column: usize) -> Grapheme {
+        match self.base_position(row, column, false) {
+            Some((row, column)) => self.base.read_grapheme(row, column),
+            None => Grapheme::from(' '),
+        }
+    }
+
+    fn read_style(&mut self, row: usize, column: usize) -> Style {
+        match self.base_position(row, column, false) {
+            Some((row, column)) => self.base.read_style(row, column),
+            None => Style::new(),
+        }
+    }
+
+    fn write_grapheme(&mut self, row: usize, column: usize, grapheme: &str, style: Style) {
+        if let Some((row, column)) = self.base_position(row, column, true) {
+            self.base.write_grapheme(row, column, grapheme, style)
+        }
+    }
+
+    fn read_lines(&mut self, row: usize, column: usize) -> LineCell {
+        match self.base_position(row, column, false) {
+            Some((row, column)) => self.base.read_lines(row, column),
+            None => LineCell::new(),
+        }
+    }
+
+    fn write_lines(&mut self, row: usize, column: usize, lines: LineCell, style: Style) {
+        if let Some((row, column)) = self.base_position(row, column, true) {
+            self.base.write_lines(row, column, lines, style)
+        }
+    }
+}
+
+/// Gives a view onto a rectangle of an AsciiCanvas. Writes that fall
+/// outside the rectangle (or past the edge of the view beneath) are
+/// discarded, and reads there give spaces, so a component can draw
+/// without knowing how much room its parent really gave it. You can
+/// get one of these by calling the `clip()` method on any ASCII view.
+pub struct ClipView<'canvas, V: ?Sized = dyn AsciiView + 'canvas> {
+    base: &'canvas mut V,
+    rect: Rect,
+}
+
+impl<'canvas, V: AsciiView + ?Sized> ClipView<'canvas, V> {
+    fn new(base: &'canvas mut V, rect: Rect) -> Self {
+        ClipView { base, rect }
+    }
+
+    /// Where the given cell lies in the base view, or `None` if it
+    /// lies outside the rectangle, or past the edge of the base view.
+    fn base_position(&self, row: usize, column: usize) -> Option<(usize, usize)> {
+        if row < self.rect.height && column < self.rect.width {
+            let (row, column) = (self.rect.row + row, self.rect.column + column);
+            self.base.check_bounds(row, column).ok()?;
+            Some((row, column))
+        } else {
+            None
+        }
+    }
+}
+
+impl<'canvas, V: AsciiView + ?Sized> AsciiView for ClipView<'canvas, V> {
+    /// The width of the rectangle, less any part of it that lies past
+    /// the edge of the base view.
+    fn columns(&self) -> usize {
+        columns_in_bounds(self.rect.width, |column| {
+            let column = self.rect.column + column;
+            self.base.check_bounds(self.rect.row, column).is_ok()
+        })
+    }
+
+    fn charset(&self) -> Charset {
+        self.base.charset()
+    }
+
+    fn sanitize(&self) -> Sanitize {
+        self.base.sanitize()
+    }
+
+    /// Cells outside the rectangle are fine, since anything written
+    /// there is dropped; the base view decides about those inside it.
+    fn check_bounds(&self, row: usize, column: usize) -> Result<(), CanvasError> {
+        if row < self.rect.height && column < self.rect.width {
+            self.base
+                .check_bounds(self.rect.row + row, self.rect.column + column)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn read_char(&mut self, row: usize, column: usize) -> char {
+        match self.base_position(row, column) {
+            Some((row, column)) => self.base.read_char(row, column),
+            None => ' ',
+        }
+    }
+
+    fn write_char(&mut self, row: usize, column: usize, ch: char, style: Style) {
+        self.write_grapheme(row, column, ch.encode_utf8(&mut [0; 4]), style)
+    }
+
+    fn read_grapheme(&mut self, row: usize, column: usize) -> Grapheme {
+        match self.base_position(row, column) {
+            Some((row, column)) => self.base.read_grapheme(row, column),
+            None => Grapheme::from(' '),
+        }
+    }
+
+    fn read_style(&mut self, row: usize, column: usize) -> Style {
+        match self.base_position(row, column) {
+            Some((row, column)) => self.base.read_style(row, column),
+            None => Style::new(),
+        }
+    }
+
+    /// A wide character that would hang over the right edge of the
+    /// rectangle (or of the base view) is replaced with a space.
+    fn write_grapheme(&mut self, row: usize, column: usize, grapheme: &str, style: Style) {
+        if let Some((base_row, base_column)) = self.base_position(row, column) {
+            let wide = text_width(grapheme) == 2;
+            let grapheme = if wide && self.base_position(row, column + 1).is_none() {
+                " "
+            } else {
+                grapheme
+            };
+            self.base
+                .write_grapheme(base_row, base_column, grapheme, style)
+        }
+    }
+
+    fn read_lines(&mut self, row: usize, column: usize) -> LineCell {
+        match self.base_position(row, column) {
+            Some((row, column)) => self.base.read_lines(row, column),
+            None => LineCell::new(),
+        }
+    }
+
+    fn write_lines(&mut self, row: usize, column: usize, lines: LineCell, style: Style) {
+        if let Some((row, column)) = self.base_position(row, column) {
+            self.base.write_lines(row, column, lines, style)
+        }
+    }
+}
+
+/// Gives a view onto a rectangle of an AsciiCanvas that is flipped or
+/// turned, so that, for example, a tree laid out top down can be drawn
+/// left to right. Writes that fall outside the rectangle (or past the
+/// edge of the view beneath) are discarded, as with a `ClipView`. Wide
+/// characters are not turned: they still take up the cell to their
+/// right in the canvas. You can get one of these by calling the
+/// `transform()` method on any ASCII view.
+pub struct TransformView<'canvas, V: ?Sized = dyn AsciiView + 'canvas> {
+    base: &'canvas mut V,
+    rect: Rect,
+    transform: Transform,
+}
+
+impl<'canvas, V: AsciiView + ?Sized> TransformView<'canvas, V> {
+    fn new(base: &'canvas mut V, rect: Rect, transform: Transform) -> Self {
+        TransformView {
+            base,
+            rect,
+            transform,
+        }
+    }
+
+    /// Where the given cell lies in the base view, or `None` if it
+    /// lies outside the rectangle, or past the edge of the base view.
+    fn base_position(&self, row: usize, column: usize) -> Option<(usize, usize)> {
+        let (row, column) = self.rect_position(row, column)?;
+        self.base.check_bounds(row, column).ok()?;
+        Some((row, column))
+    }
+
+    /// Where the given cell lies in the base view, or `None` if it
+    /// lies outside the rectangle.
+    fn rect_position(&self, row: usize, column: usize) -> Option<(usize, usize)> {
+        let (transpose, flip_rows, flip_columns) = self.transform.steps();
+        let (row, column) = if transpose {
+            (column, row)
+        } else {
+            (row, column)
+        };
+        if row >= self.rect.height || column >= self.rect.width {
+            return None;
+        }
+        let row = if flip_rows {
+            self.rect.height - 1 - row
+        } else {
+            row
+        };
+        let column = if flip_columns {
+            self.rect.width - 1 - column
+        } else {
+            column
+        };
+        Some((self.rect.row + row, self.rect.column + column))
+    }
+}
+
+impl<'canvas, V: AsciiView + ?Sized> AsciiView for TransformView<'canvas, V> {
+    /// The number of columns, counting from the left, that land within
+    /// the base view (all of them, unless the rectangle runs past its
+    /// edge).
+    fn columns(&self) -> usize {
+        let columns = if self.transform.transposes() {
+            self.rect.height
+        } else {
+            self.rect.width
+        };
+        // a mirrored rectangle that runs past the edge has its missing
+        // cells on the left, and then no columns count
+        if self.base_position(0, 0).is_none() {
+            return 0;
+        }
+        columns_in_bounds(columns, |column| self.base_position(0, column).is_some())
+    }
+
+    fn charset(&self) -> Charset {
+        self.base.charset()
+    }
+
+    fn sanitize(&self) -> Sanitize {
+        self.base.sanitize()
+    }
+
+    /// As with a `ClipView`, cells outside the rectangle are fine; a
+    /// cell inside it is checked where it lands in the base view.
+    fn check_bounds(&self, row: usize, column: usize) -> Result<(), CanvasError> {
+        match self.rect_position(row, column) {
+            Some((row, column)) => self.base.check_bounds(row, column),
+            None => Ok(()),
+        }
+    }
+
+    fn read_char(&mut self, row: usize, column: usize) -> char {
+        match self.base_position(row, column) {
+            Some((row, column)) => {
+                let ch = self.base.read_char(row, column);
+                self.transform.inverse().map_char(ch)
+            }
+            None => ' ',
+        }
+    }
+
+    fn write_char(&mut self, row: usize, column: usize, ch: char, style: Style) {
+        self.write_grapheme(row, column, ch.encode_utf8(&mut [0; 4]), style)
+    }
+
+    fn read_grapheme(&mut self, row: usize, column: usize) -> Grapheme {
+        match self.base_position(row, column) {
+            Some((row, column)) => {
+                let grapheme = self.base.read_grapheme(row, column);
+                Grapheme::new(&self.transform.inverse().map_grapheme(&grapheme))
+            }
+            None => Grapheme::from(' '),
+        }
+    }
+
+    /// A wide character whose second cell would land outside the
+    /// rectangle (or past the edge of the base view) is replaced with a
+    /// space.
+    fn write_grapheme(&mut self, row: usize, column: usize, grapheme: &str, style: Style) {
+        if let Some((base_row, base_column)) = self.base_position(row, column) {
+            let next_column = base_column + 1;
+            let fits = next_column < self.rect.end_column()
+                && self.base.check_bounds(base_row, next_column).is_ok();
+            let grapheme = if text_width(grapheme) == 2 && !fits {
+                " ".to_string()
+            } else {
+                self.transform.map_grapheme(grapheme)
+            };
+            self.base
+                .write_grapheme(base_row, base_column, &grapheme, style)
+        }
+    }
+
+    fn read_style(&mut self, row: usize, column: usize) -> Style {
+        match self.base_position(row, column) {
+            Some((row, column)) => self.base.read_style(row, column),
+            None => Style::new(),
+        }
+    }
+
+    fn read_lines(&mut self, row: usize, column: usize) -> LineCell {
+        match self.base_position(row, column) {
+            Some((row, column)) => {
+                let lines = self.base.read_lines(row, column);
+                lines.transformed(self.transform.inverse())
+            }
+            None => LineCell::new(),
+        }
+    }
+
+    fn write_lines(&mut self, row: usize, column: usize, lines: LineCell, style: Style) {
+        if let Some((row, column)) = self.base_position(row, column) {
+            let lines = lines.transformed(self.transform);
+            self.base.write_lines(row, column, lines, style)
+        }
+    }
 }
 
 /// Gives a view onto an AsciiCanvas that applies an additional style
 /// to things that are written. You can get one of these by calling
 /// the `styled()` method on any ASCII view.
-pub struct StyleView<'canvas> {
-    base: &'canvas mut dyn AsciiView,
+pub struct StyleView<'canvas, V: ?Sized = dyn AsciiView + 'canvas> {
+    base: &'canvas mut V,
     style: Style,
 }
 
-impl<'canvas> StyleView<'canvas> {
-    fn new(base: &'canvas mut dyn AsciiView, style: Style) -> Self {
-        StyleView {
-            base: base,
-            style: style,
-        }
+impl<'canvas, V: AsciiView + ?Sized> StyleView<'canvas, V> {
+    fn new(base: &'canvas mut V, style: Style) -> Self {
+        StyleView { base, style }
     }
 }
 
-impl<'canvas> AsciiView for StyleView<'canvas> {
+impl<'canvas, V: AsciiView + ?Sized> AsciiView for StyleView<'canvas, V> {
     fn columns(&self) -> usize {
         self.base.columns()
     }
 
+    fn charset(&self) -> Charset {
+        self.base.charset()
+    }
+
+    fn sanitize(&self) -> Sanitize {
+        self.base.sanitize()
+    }
+
+    fn check_bounds(&self, row: usize, column: usize) -> Result<(), CanvasError> {
+        self.base.check_bounds(row, column)
+    }
+
+    fn grow_up_left(&mut self, rows: usize, columns: usize) -> bool {
+        self.base.grow_up_left(rows, columns)
+    }
+
     fn read_char(&mut self, row: usize, column: usize) -> char {
         self.base.read_char(row, column)
     }
@@ -285,58 +2144,28 @@ impl<'canvas> AsciiView for StyleView<'canvas> {
         self.base
             .write_char(row, column, ch, style.with(self.style))
     }
-}
 
-///////////////////////////////////////////////////////////////////////////
-// Unicode box-drawing characters
-
-const UP: u8 = 0b0001;
-const DOWN: u8 = 0b0010;
-const LEFT: u8 = 0b0100;
-const RIGHT: u8 = 0b1000;
-
-const BOX_CHARS: &'static [(char, u8)] = &[
-    ('╵', UP),
-    ('│', UP | DOWN),
-    ('┤', UP | DOWN | LEFT),
-    ('├', UP | DOWN | RIGHT),
-    ('┼', UP | DOWN | LEFT | RIGHT),
-    ('┘', UP | LEFT),
-    ('└', UP | RIGHT),
-    ('┴', UP | LEFT | RIGHT),
-    // No UP:
-    ('╷', DOWN),
-    ('┐', DOWN | LEFT),
-    ('┌', DOWN | RIGHT),
-    ('┬', DOWN | LEFT | RIGHT),
-    // No UP|DOWN:
-    ('╶', LEFT),
-    ('─', LEFT | RIGHT),
-    // No LEFT:
-    ('╴', RIGHT),
-    // No RIGHT:
-    (' ', 0),
-];
-
-fn box_char_for_dirs(dirs: u8) -> char {
-    for &(c, d) in BOX_CHARS {
-        if dirs == d {
-            return c;
-        }
-    }
-    panic!("no box character for dirs: {:b}", dirs);
-}
-
-fn dirs_for_box_char(ch: char) -> Option<u8> {
-    for &(c, d) in BOX_CHARS {
-        if c == ch {
-            return Some(d);
-        }
-    }
-    None
-}
-
-fn add_dirs(old_ch: char, new_dirs: u8) -> char {
-    let old_dirs = dirs_for_box_char(old_ch).unwrap_or(0);
-    box_char_for_dirs(old_dirs | new_dirs)
+    fn read_lines(&mut self, row: usize, column: usize) -> LineCell {
+        self.base.read_lines(row, column)
+    }
+
+    fn write_lines(&mut self, row: usize, column: usize, lines: LineCell, style: Style) {
+        self.base
+            .write_lines(row, column, lines, style.with(self.style))
+    }
+
+    fn read_grapheme(&mut self, row: usize, column: usize) -> Grapheme {
+        self.base.read_grapheme(row, column)
+    }
+
+    /// Reports the style in the base view, not including the style
+    /// this view adds to new writes.
+    fn read_style(&mut self, row: usize, column: usize) -> Style {
+        self.base.read_style(row, column)
+    }
+
+    fn write_grapheme(&mut self, row: usize, column: usize, grapheme: &str, style: Style) {
+        self.base
+            .write_grapheme(row, column, grapheme, style.with(self.style))
+    }
 }
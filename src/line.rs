@@ -0,0 +1,727 @@
+//! Unicode box-drawing characters, and the `LineStyle` used to pick
+//! between their various weights and shapes.
+
+use crate::error::CanvasError;
+use crate::grapheme::Grapheme;
+use crate::style::Style;
+use crate::transform::{Transform, STEPS};
+
+/// Selects which family of box-drawing characters a line is drawn
+/// with. Where lines of different styles meet, the junction uses the
+/// mixed-weight character (e.g., `┿` or `╪`) when one exists.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum LineStyle {
+    /// `─`, `│`, `┌`, ...
+    #[default]
+    Light,
+    /// `━`, `┃`, `┏`, ...
+    Heavy,
+    /// `═`, `║`, `╔`, ...
+    Double,
+    /// Light lines, but with rounded corners: `╭`, `╮`, `╯`, `╰`.
+    Rounded,
+    /// Light lines, but dashed: `┄`, `┆`. There are no dashed
+    /// half-lines, so the cells at the ends of a dashed line are drawn
+    /// in full.
+    Dashed,
+}
+
+impl LineStyle {
+    fn weight(self) -> u8 {
+        match self {
+            LineStyle::Heavy => HEAVY,
+            LineStyle::Double => DOUBLE,
+            LineStyle::Light | LineStyle::Rounded | LineStyle::Dashed => LIGHT,
+        }
+    }
+}
+
+/// A marker drawn at the start or end of a line. Arrows point away
+/// from the line, so an arrow at the end of a line drawn left to right
+/// is `▶`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Marker {
+    /// The line just stops.
+    #[default]
+    None,
+    /// `▶`, `◀`, `▲` or `▼`.
+    Arrow,
+    /// `→`, `←`, `↑` or `↓`.
+    ThinArrow,
+    /// `●`
+    Dot,
+    /// `◆`
+    Diamond,
+}
+
+impl Marker {
+    /// The character for this marker at the end of a line that leaves
+    /// its last cell in direction `dir` (one of `UP`, `DOWN`, `LEFT`
+    /// or `RIGHT`).
+    pub(crate) fn to_char(self, dir: u8) -> Option<char> {
+        let arrows = match self {
+            Marker::None => return None,
+            Marker::Arrow => ['▲', '▼', '◀', '▶'],
+            Marker::ThinArrow => ['↑', '↓', '←', '→'],
+            Marker::Dot => return Some('●'),
+            Marker::Diamond => return Some('◆'),
+        };
+        let index = [UP, DOWN, LEFT, RIGHT].iter().position(|&d| d == dir)?;
+        Some(arrows[index])
+    }
+
+    /// Recovers a marker, and the direction it points in (`NONE` for
+    /// dots and diamonds), from its character.
+    fn from_char(ch: char) -> Option<(Marker, u8)> {
+        let dirs = [UP, DOWN, LEFT, RIGHT];
+        for &marker in &[Marker::Arrow, Marker::ThinArrow] {
+            if let Some(&dir) = dirs.iter().find(|&&d| marker.to_char(d) == Some(ch)) {
+                return Some((marker, dir));
+            }
+        }
+        match ch {
+            '●' => Some((Marker::Dot, NONE)),
+            '◆' => Some((Marker::Diamond, NONE)),
+            _ => None,
+        }
+    }
+}
+
+/// Describes how to draw a line: which box-drawing characters to use,
+/// which style to apply to them, and what to put at either end. A bare
+/// `LineStyle` converts into a pen with the default style and no
+/// markers.
+#[derive(Copy, Clone, Default, PartialEq, Eq)]
+pub struct Pen {
+    pub line_style: LineStyle,
+    pub style: Style,
+    pub start: Marker,
+    pub end: Marker,
+}
+
+impl Pen {
+    pub fn new(line_style: LineStyle, style: Style) -> Pen {
+        Pen {
+            line_style,
+            style,
+            start: Marker::None,
+            end: Marker::None,
+        }
+    }
+
+    /// Returns a copy of this pen that draws the given markers at the
+    /// start and end of each line.
+    pub fn with_markers(self, start: Marker, end: Marker) -> Pen {
+        Pen { start, end, ..self }
+    }
+
+    /// Returns a copy of this pen with the markers swapped, for drawing
+    /// a line back to front.
+    pub(crate) fn reversed(self) -> Pen {
+        self.with_markers(self.end, self.start)
+    }
+}
+
+impl From<LineStyle> for Pen {
+    fn from(line_style: LineStyle) -> Pen {
+        Pen::new(line_style, Style::new())
+    }
+}
+
+/// Decides the style of a cell where lines of different styles meet.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub enum JunctionRule {
+    /// The line drawn first keeps its style.
+    FirstWins,
+    /// The line drawn last imposes its style (the default).
+    #[default]
+    LastWins,
+    /// Whichever style comes earlier in the list wins; styles that do
+    /// not appear in the list lose to those that do.
+    Priority(Vec<Style>),
+    /// Junctions always get this style.
+    Fixed(Style),
+}
+
+impl JunctionRule {
+    pub(crate) fn resolve(&self, old: Style, new: Style) -> Style {
+        match self {
+            JunctionRule::FirstWins => old,
+            JunctionRule::LastWins => new,
+            JunctionRule::Priority(order) => {
+                let rank = |style| order.iter().position(|&s| s == style);
+                match (rank(old), rank(new)) {
+                    (Some(o), Some(n)) if o < n => old,
+                    (Some(_), None) => old,
+                    _ => new,
+                }
+            }
+            JunctionRule::Fixed(style) => *style,
+        }
+    }
+}
+
+/// Selects the characters a canvas is rendered with.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Charset {
+    /// Unicode box-drawing characters.
+    #[default]
+    Unicode,
+    /// Plain ASCII, for terminals and logs that mangle Unicode: lines
+    /// are drawn with `-`, `=`, `|`, `/` and `\\`, every corner or
+    /// junction becomes `+`, and arrows become `^`, `v`, `<` and `>`.
+    Ascii,
+}
+
+impl Charset {
+    /// Converts a character as stored in the canvas into the character
+    /// to emit. Anything that is not a box-drawing character is left
+    /// alone.
+    pub(crate) fn convert(self, ch: char) -> char {
+        match self {
+            Charset::Unicode => ch,
+            Charset::Ascii => match dirs_for_box_char(ch) {
+                Some((dirs, _)) => ascii_char_for_dirs(dirs),
+                None => ascii_char_for_symbol(ch),
+            },
+        }
+    }
+
+    /// The ellipsis used to mark where text was cut short.
+    pub fn ellipsis(self) -> &'static str {
+        match self {
+            Charset::Unicode => "…",
+            Charset::Ascii => "...",
+        }
+    }
+
+    /// Like `convert`, for a cell holding a whole grapheme cluster; only
+    /// clusters of a single `char` can be box-drawing characters.
+    pub(crate) fn convert_grapheme(self, grapheme: Grapheme) -> Grapheme {
+        let mut chars = grapheme.chars();
+        match (chars.next(), chars.next()) {
+            (Some(ch), None) => Grapheme::from(self.convert(ch)),
+            _ => grapheme,
+        }
+    }
+}
+
+/// The lines passing through a single cell of the canvas: which
+/// directions they leave the cell in, how heavy each one is, and the
+/// style of the line that was drawn there most recently (which decides
+/// between, e.g., `└` and `╰`). Diagonal lines are tracked too, but
+/// they only show up in cells with no horizontal or vertical line. A
+/// marker at the end of a line shows up in place of the line.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct LineCell {
+    dirs: u8,
+    diagonals: u8,
+    line_style: LineStyle,
+    marker: Marker,
+    /// The direction the marker points in (away from its line).
+    marker_dir: u8,
+}
+
+impl LineCell {
+    /// A cell with no lines in it.
+    pub fn new() -> LineCell {
+        LineCell::default()
+    }
+
+    /// Recovers the lines from a box-drawing character (or a marker);
+    /// any other character yields an empty cell.
+    pub fn from_char(ch: char) -> LineCell {
+        if let Some((marker, marker_dir)) = Marker::from_char(ch) {
+            return LineCell {
+                marker,
+                marker_dir,
+                ..LineCell::default()
+            };
+        }
+        if let Some((dirs, line_style)) = dirs_for_box_char(ch) {
+            return LineCell {
+                dirs,
+                line_style,
+                ..LineCell::default()
+            };
+        }
+        let diagonals = diagonals_for_char(ch).unwrap_or(NONE);
+        LineCell {
+            diagonals,
+            ..LineCell::default()
+        }
+    }
+
+    /// Like `from_char`, but returns an error for characters other
+    /// than box-drawing characters, diagonals, markers and spaces.
+    pub fn try_from_char(ch: char) -> Result<LineCell, CanvasError> {
+        let known = ch == ' '
+            || dirs_for_box_char(ch).is_some()
+            || diagonals_for_char(ch).is_some()
+            || Marker::from_char(ch).is_some();
+        if known {
+            Ok(LineCell::from_char(ch))
+        } else {
+            Err(CanvasError::UnknownGlyph(ch))
+        }
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.dirs == NONE && self.diagonals == NONE && self.marker == Marker::None
+    }
+
+    /// The box-drawing character for these lines (a space if there
+    /// are none).
+    pub fn to_char(self) -> char {
+        if let Some(ch) = self.marker.to_char(self.marker_dir) {
+            return ch;
+        }
+        let slashes = self.diagonals & SLASHES != 0;
+        match (self.dirs, self.diagonals & RISING_AND_FALLING, slashes) {
+            (NONE, RISING, false) => '╱',
+            (NONE, FALLING, false) => '╲',
+            (NONE, RISING_AND_FALLING, false) => '╳',
+            (NONE, RISING, true) => '/',
+            (NONE, FALLING, true) => '\\',
+            (NONE, RISING_AND_FALLING, true) => 'X',
+            (dirs, _, _) => box_char_for_dirs(dirs, self.line_style),
+        }
+    }
+
+    /// Adds lines leaving in the given (light) directions, drawn in
+    /// the given style.
+    pub(crate) fn add(self, dirs: u8, line_style: LineStyle) -> LineCell {
+        LineCell {
+            dirs: merge_dirs(self.dirs, weighted(dirs, line_style)),
+            line_style,
+            ..self
+        }
+    }
+
+    /// Adds a diagonal line, either `RISING` or `FALLING` (possibly
+    /// with `SLASHES`).
+    pub(crate) fn add_diagonal(self, diagonal: u8, line_style: LineStyle) -> LineCell {
+        LineCell {
+            diagonals: self.diagonals | diagonal,
+            line_style,
+            ..self
+        }
+    }
+
+    /// Puts a marker at the end of the line here, pointing in direction
+    /// `dir`.
+    pub(crate) fn add_marker(self, marker: Marker, dir: u8) -> LineCell {
+        LineCell {
+            marker,
+            marker_dir: dir,
+            ..self
+        }
+    }
+
+    /// Adds all the lines in `other`, which take over the line style
+    /// (and the marker, if `other` has one).
+    pub(crate) fn merge(self, other: LineCell) -> LineCell {
+        let has_lines = other.dirs != NONE || other.diagonals != NONE;
+        let (marker, marker_dir) = if other.marker == Marker::None {
+            (self.marker, self.marker_dir)
+        } else {
+            (other.marker, other.marker_dir)
+        };
+        LineCell {
+            dirs: merge_dirs(self.dirs, other.dirs),
+            diagonals: self.diagonals | other.diagonals,
+            line_style: if has_lines {
+                other.line_style
+            } else {
+                self.line_style
+            },
+            marker,
+            marker_dir,
+        }
+    }
+
+    /// The same lines, flipped or turned by `transform`.
+    pub(crate) fn transformed(self, transform: Transform) -> LineCell {
+        let map_index = |index: usize| {
+            let step = transform.map_step(STEPS[index]);
+            STEPS.iter().position(|&s| s == step).unwrap()
+        };
+        let mut new_weights = [NONE; 4];
+        for (index, &weight) in weights(self.dirs).iter().enumerate() {
+            new_weights[map_index(index)] = weight;
+        }
+        let dirs = [UP, DOWN, LEFT, RIGHT];
+        let marker_dir = match dirs.iter().position(|&d| d == self.marker_dir) {
+            Some(index) => dirs[map_index(index)],
+            None => self.marker_dir,
+        };
+        let mut diagonals = self.diagonals;
+        if transform.swaps_diagonals() {
+            let (rising, falling) = (diagonals & RISING, diagonals & FALLING);
+            diagonals = (diagonals & SLASHES) | (rising << 1) | (falling >> 1);
+        }
+        LineCell {
+            dirs: from_weights(new_weights),
+            diagonals,
+            marker_dir,
+            ..self
+        }
+    }
+
+    /// True if every line in `other` is also in `self` (with the same
+    /// weight).
+    pub(crate) fn contains(self, other: LineCell) -> bool {
+        merge_dirs(self.dirs, other.dirs) == self.dirs
+            && (self.diagonals | other.diagonals) == self.diagonals
+            && (other.marker == Marker::None
+                || (other.marker, other.marker_dir) == (self.marker, self.marker_dir))
+    }
+
+    /// Removes any lines leaving in the given directions, whatever
+    /// their weight, along with the marker on the end of any of them.
+    pub(crate) fn remove(self, dirs: u8) -> LineCell {
+        let mut weights = weights(self.dirs);
+        for (weight, &removed) in weights.iter_mut().zip(&self::weights(dirs)) {
+            if removed != NONE {
+                *weight = NONE;
+            }
+        }
+        let mut cell = LineCell {
+            dirs: from_weights(weights),
+            ..self
+        };
+        // the marker points away from the line it ends
+        let line_dir = opposite(self.marker_dir);
+        if dirs & line_dir != NONE || (cell.dirs == NONE && cell.diagonals == NONE) {
+            cell.marker = Marker::None;
+            cell.marker_dir = NONE;
+        }
+        if cell.is_empty() {
+            LineCell::new()
+        } else {
+            cell
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Directions
+//
+// Each direction gets two bits holding the weight of the line that
+// leaves the cell that way (`NONE` meaning there is no such line).
+// The `UP`, `DOWN`, `LEFT` and `RIGHT` constants are light lines; use
+// `weighted` to get the heavy or double equivalent.
+
+const NONE: u8 = 0;
+const LIGHT: u8 = 1;
+const HEAVY: u8 = 2;
+const DOUBLE: u8 = 3;
+
+const WEIGHT_MASK: u8 = 0b11;
+const SHIFTS: [u8; 4] = [0, 2, 4, 6];
+
+pub(crate) const UP: u8 = dirs(LIGHT, NONE, NONE, NONE);
+pub(crate) const DOWN: u8 = dirs(NONE, LIGHT, NONE, NONE);
+pub(crate) const LEFT: u8 = dirs(NONE, NONE, LIGHT, NONE);
+pub(crate) const RIGHT: u8 = dirs(NONE, NONE, NONE, LIGHT);
+
+// Diagonals get a bit each: `RISING` runs from lower-left to
+// upper-right (`╱`), `FALLING` from upper-left to lower-right (`╲`).
+// Lines at exactly 45° also set `SLASHES`, and are drawn with `/`
+// and `\` instead.
+
+pub(crate) const RISING: u8 = 0b001;
+pub(crate) const FALLING: u8 = 0b010;
+pub(crate) const SLASHES: u8 = 0b100;
+const RISING_AND_FALLING: u8 = RISING | FALLING;
+
+const fn dirs(up: u8, down: u8, left: u8, right: u8) -> u8 {
+    up | (down << 2) | (left << 4) | (right << 6)
+}
+
+/// The opposite of a single light direction.
+fn opposite(dir: u8) -> u8 {
+    match dir {
+        UP => DOWN,
+        DOWN => UP,
+        LEFT => RIGHT,
+        RIGHT => LEFT,
+        _ => NONE,
+    }
+}
+
+/// Converts light directions (some combination of `UP`, `DOWN`,
+/// `LEFT` and `RIGHT`) into directions drawn in the given style.
+fn weighted(dirs: u8, line_style: LineStyle) -> u8 {
+    // every field of `dirs` is either 0 or 1, so this cannot carry
+    dirs * line_style.weight()
+}
+
+fn weights(dirs: u8) -> [u8; 4] {
+    let mut weights = [NONE; 4];
+    for (weight, &shift) in weights.iter_mut().zip(&SHIFTS) {
+        *weight = (dirs >> shift) & WEIGHT_MASK;
+    }
+    weights
+}
+
+fn from_weights(weights: [u8; 4]) -> u8 {
+    dirs(weights[0], weights[1], weights[2], weights[3])
+}
+
+/// Combines two sets of directions; where both have a line going the
+/// same way, the heavier one wins.
+fn merge_dirs(a: u8, b: u8) -> u8 {
+    let mut merged = weights(a);
+    for (m, &w) in merged.iter_mut().zip(&weights(b)) {
+        *m = (*m).max(w);
+    }
+    from_weights(merged)
+}
+
+/// Replaces every line of weight `from` with a light line.
+fn lighten(dirs: u8, from: u8) -> u8 {
+    let mut weights = weights(dirs);
+    for weight in &mut weights {
+        if *weight == from {
+            *weight = LIGHT;
+        }
+    }
+    from_weights(weights)
+}
+
+/// If there is a line in only one direction, extends it through the
+/// cell (there are no double half-lines, so `═` stands in for one).
+fn extend_stub(dirs: u8) -> u8 {
+    let [up, down, left, right] = weights(dirs);
+    match (up, down, left, right) {
+        (w, NONE, NONE, NONE) | (NONE, w, NONE, NONE) => self::dirs(w, w, NONE, NONE),
+        (NONE, NONE, w, NONE) | (NONE, NONE, NONE, w) => self::dirs(NONE, NONE, w, w),
+        _ => dirs,
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Characters
+
+const BOX_CHARS: &[(char, u8)] = &[
+    (' ', 0),
+    ('─', dirs(NONE, NONE, LIGHT, LIGHT)),
+    ('━', dirs(NONE, NONE, HEAVY, HEAVY)),
+    ('│', dirs(LIGHT, LIGHT, NONE, NONE)),
+    ('┃', dirs(HEAVY, HEAVY, NONE, NONE)),
+    ('┌', dirs(NONE, LIGHT, NONE, LIGHT)),
+    ('┍', dirs(NONE, LIGHT, NONE, HEAVY)),
+    ('┎', dirs(NONE, HEAVY, NONE, LIGHT)),
+    ('┏', dirs(NONE, HEAVY, NONE, HEAVY)),
+    ('┐', dirs(NONE, LIGHT, LIGHT, NONE)),
+    ('┑', dirs(NONE, LIGHT, HEAVY, NONE)),
+    ('┒', dirs(NONE, HEAVY, LIGHT, NONE)),
+    ('┓', dirs(NONE, HEAVY, HEAVY, NONE)),
+    ('└', dirs(LIGHT, NONE, NONE, LIGHT)),
+    ('┕', dirs(LIGHT, NONE, NONE, HEAVY)),
+    ('┖', dirs(HEAVY, NONE, NONE, LIGHT)),
+    ('┗', dirs(HEAVY, NONE, NONE, HEAVY)),
+    ('┘', dirs(LIGHT, NONE, LIGHT, NONE)),
+    ('┙', dirs(LIGHT, NONE, HEAVY, NONE)),
+    ('┚', dirs(HEAVY, NONE, LIGHT, NONE)),
+    ('┛', dirs(HEAVY, NONE, HEAVY, NONE)),
+    ('├', dirs(LIGHT, LIGHT, NONE, LIGHT)),
+    ('┝', dirs(LIGHT, LIGHT, NONE, HEAVY)),
+    ('┞', dirs(HEAVY, LIGHT, NONE, LIGHT)),
+    ('┟', dirs(LIGHT, HEAVY, NONE, LIGHT)),
+    ('┠', dirs(HEAVY, HEAVY, NONE, LIGHT)),
+    ('┡', dirs(HEAVY, LIGHT, NONE, HEAVY)),
+    ('┢', dirs(LIGHT, HEAVY, NONE, HEAVY)),
+    ('┣', dirs(HEAVY, HEAVY, NONE, HEAVY)),
+    ('┤', dirs(LIGHT, LIGHT, LIGHT, NONE)),
+    ('┥', dirs(LIGHT, LIGHT, HEAVY, NONE)),
+    ('┦', dirs(HEAVY, LIGHT, LIGHT, NONE)),
+    ('┧', dirs(LIGHT, HEAVY, LIGHT, NONE)),
+    ('┨', dirs(HEAVY, HEAVY, LIGHT, NONE)),
+    ('┩', dirs(HEAVY, LIGHT, HEAVY, NONE)),
+    ('┪', dirs(LIGHT, HEAVY, HEAVY, NONE)),
+    ('┫', dirs(HEAVY, HEAVY, HEAVY, NONE)),
+    ('┬', dirs(NONE, LIGHT, LIGHT, LIGHT)),
+    ('┭', dirs(NONE, LIGHT, HEAVY, LIGHT)),
+    ('┮', dirs(NONE, LIGHT, LIGHT, HEAVY)),
+    ('┯', dirs(NONE, LIGHT, HEAVY, HEAVY)),
+    ('┰', dirs(NONE, HEAVY, LIGHT, LIGHT)),
+    ('┱', dirs(NONE, HEAVY, HEAVY, LIGHT)),
+    ('┲', dirs(NONE, HEAVY, LIGHT, HEAVY)),
+    ('┳', dirs(NONE, HEAVY, HEAVY, HEAVY)),
+    ('┴', dirs(LIGHT, NONE, LIGHT, LIGHT)),
+    ('┵', dirs(LIGHT, NONE, HEAVY, LIGHT)),
+    ('┶', dirs(LIGHT, NONE, LIGHT, HEAVY)),
+    ('┷', dirs(LIGHT, NONE, HEAVY, HEAVY)),
+    ('┸', dirs(HEAVY, NONE, LIGHT, LIGHT)),
+    ('┹', dirs(HEAVY, NONE, HEAVY, LIGHT)),
+    ('┺', dirs(HEAVY, NONE, LIGHT, HEAVY)),
+    ('┻', dirs(HEAVY, NONE, HEAVY, HEAVY)),
+    ('┼', dirs(LIGHT, LIGHT, LIGHT, LIGHT)),
+    ('┽', dirs(LIGHT, LIGHT, HEAVY, LIGHT)),
+    ('┾', dirs(LIGHT, LIGHT, LIGHT, HEAVY)),
+    ('┿', dirs(LIGHT, LIGHT, HEAVY, HEAVY)),
+    ('╀', dirs(HEAVY, LIGHT, LIGHT, LIGHT)),
+    ('╁', dirs(LIGHT, HEAVY, LIGHT, LIGHT)),
+    ('╂', dirs(HEAVY, HEAVY, LIGHT, LIGHT)),
+    ('╃', dirs(HEAVY, LIGHT, HEAVY, LIGHT)),
+    ('╄', dirs(HEAVY, LIGHT, LIGHT, HEAVY)),
+    ('╅', dirs(LIGHT, HEAVY, HEAVY, LIGHT)),
+    ('╆', dirs(LIGHT, HEAVY, LIGHT, HEAVY)),
+    ('╇', dirs(HEAVY, LIGHT, HEAVY, HEAVY)),
+    ('╈', dirs(LIGHT, HEAVY, HEAVY, HEAVY)),
+    ('╉', dirs(HEAVY, HEAVY, HEAVY, LIGHT)),
+    ('╊', dirs(HEAVY, HEAVY, LIGHT, HEAVY)),
+    ('╋', dirs(HEAVY, HEAVY, HEAVY, HEAVY)),
+    ('═', dirs(NONE, NONE, DOUBLE, DOUBLE)),
+    ('║', dirs(DOUBLE, DOUBLE, NONE, NONE)),
+    ('╒', dirs(NONE, LIGHT, NONE, DOUBLE)),
+    ('╓', dirs(NONE, DOUBLE, NONE, LIGHT)),
+    ('╔', dirs(NONE, DOUBLE, NONE, DOUBLE)),
+    ('╕', dirs(NONE, LIGHT, DOUBLE, NONE)),
+    ('╖', dirs(NONE, DOUBLE, LIGHT, NONE)),
+    ('╗', dirs(NONE, DOUBLE, DOUBLE, NONE)),
+    ('╘', dirs(LIGHT, NONE, NONE, DOUBLE)),
+    ('╙', dirs(DOUBLE, NONE, NONE, LIGHT)),
+    ('╚', dirs(DOUBLE, NONE, NONE, DOUBLE)),
+    ('╛', dirs(LIGHT, NONE, DOUBLE, NONE)),
+    ('╜', dirs(DOUBLE, NONE, LIGHT, NONE)),
+    ('╝', dirs(DOUBLE, NONE, DOUBLE, NONE)),
+    ('╞', dirs(LIGHT, LIGHT, NONE, DOUBLE)),
+    ('╟', dirs(DOUBLE, DOUBLE, NONE, LIGHT)),
+    ('╠', dirs(DOUBLE, DOUBLE, NONE, DOUBLE)),
+    ('╡', dirs(LIGHT, LIGHT, DOUBLE, NONE)),
+    ('╢', dirs(DOUBLE, DOUBLE, LIGHT, NONE)),
+    ('╣', dirs(DOUBLE, DOUBLE, DOUBLE, NONE)),
+    ('╤', dirs(NONE, LIGHT, DOUBLE, DOUBLE)),
+    ('╥', dirs(NONE, DOUBLE, LIGHT, LIGHT)),
+    ('╦', dirs(NONE, DOUBLE, DOUBLE, DOUBLE)),
+    ('╧', dirs(LIGHT, NONE, DOUBLE, DOUBLE)),
+    ('╨', dirs(DOUBLE, NONE, LIGHT, LIGHT)),
+    ('╩', dirs(DOUBLE, NONE, DOUBLE, DOUBLE)),
+    ('╪', dirs(LIGHT, LIGHT, DOUBLE, DOUBLE)),
+    ('╫', dirs(DOUBLE, DOUBLE, LIGHT, LIGHT)),
+    ('╬', dirs(DOUBLE, DOUBLE, DOUBLE, DOUBLE)),
+    ('╴', dirs(NONE, NONE, LIGHT, NONE)),
+    ('╵', dirs(LIGHT, NONE, NONE, NONE)),
+    ('╶', dirs(NONE, NONE, NONE, LIGHT)),
+    ('╷', dirs(NONE, LIGHT, NONE, NONE)),
+    ('╸', dirs(NONE, NONE, HEAVY, NONE)),
+    ('╹', dirs(HEAVY, NONE, NONE, NONE)),
+    ('╺', dirs(NONE, NONE, NONE, HEAVY)),
+    ('╻', dirs(NONE, HEAVY, NONE, NONE)),
+    ('╼', dirs(NONE, NONE, LIGHT, HEAVY)),
+    ('╽', dirs(LIGHT, HEAVY, NONE, NONE)),
+    ('╾', dirs(NONE, NONE, HEAVY, LIGHT)),
+    ('╿', dirs(HEAVY, LIGHT, NONE, NONE)),
+];
+
+const ROUNDED_CHARS: &[(char, u8)] = &[
+    ('╭', dirs(NONE, LIGHT, NONE, LIGHT)),
+    ('╮', dirs(NONE, LIGHT, LIGHT, NONE)),
+    ('╯', dirs(LIGHT, NONE, LIGHT, NONE)),
+    ('╰', dirs(LIGHT, NONE, NONE, LIGHT)),
+];
+
+const DASHED_CHARS: &[(char, u8)] = &[
+    ('┄', dirs(NONE, NONE, LIGHT, LIGHT)),
+    ('┅', dirs(NONE, NONE, HEAVY, HEAVY)),
+    ('┆', dirs(LIGHT, LIGHT, NONE, NONE)),
+    ('┇', dirs(HEAVY, HEAVY, NONE, NONE)),
+    // We never produce these, but recognize them when merging:
+    ('┈', dirs(NONE, NONE, LIGHT, LIGHT)),
+    ('┉', dirs(NONE, NONE, HEAVY, HEAVY)),
+    ('┊', dirs(LIGHT, LIGHT, NONE, NONE)),
+    ('┋', dirs(HEAVY, HEAVY, NONE, NONE)),
+    ('╌', dirs(NONE, NONE, LIGHT, LIGHT)),
+    ('╍', dirs(NONE, NONE, HEAVY, HEAVY)),
+    ('╎', dirs(LIGHT, LIGHT, NONE, NONE)),
+    ('╏', dirs(HEAVY, HEAVY, NONE, NONE)),
+];
+
+fn lookup(table: &[(char, u8)], dirs: u8) -> Option<char> {
+    table.iter().find(|&&(_, d)| d == dirs).map(|&(c, _)| c)
+}
+
+fn box_char_for_dirs(dirs: u8, line_style: LineStyle) -> char {
+    let variants = match line_style {
+        LineStyle::Rounded => ROUNDED_CHARS,
+        LineStyle::Dashed => DASHED_CHARS,
+        LineStyle::Light | LineStyle::Heavy | LineStyle::Double => &[],
+    };
+    // there are no dashed half-lines, so (like a double line) a dashed
+    // line runs right through the cells at its ends
+    if let Some(ch) = lookup(variants, dirs).or_else(|| lookup(variants, extend_stub(dirs))) {
+        return ch;
+    }
+
+    // Not every combination of weights has a character: double lines
+    // have no half-lines and never mix with heavy ones. Simplify the
+    // weights until we find something that exists; once everything is
+    // light, there is always a match.
+    let candidates = [
+        dirs,
+        extend_stub(dirs),
+        lighten(dirs, HEAVY),
+        lighten(dirs, DOUBLE),
+        lighten(lighten(dirs, HEAVY), DOUBLE),
+    ];
+    for &candidate in &candidates {
+        if let Some(ch) = lookup(BOX_CHARS, candidate) {
+            return ch;
+        }
+    }
+    panic!("no box character for dirs: {:b}", dirs);
+}
+
+fn ascii_char_for_dirs(dirs: u8) -> char {
+    let [up, down, left, right] = weights(dirs);
+    match (up.max(down), left.max(right)) {
+        (NONE, NONE) => ' ',
+        (_, NONE) => '|',
+        (NONE, DOUBLE) => '=',
+        (NONE, _) => '-',
+        (_, _) => '+',
+    }
+}
+
+fn ascii_char_for_symbol(ch: char) -> char {
+    match ch {
+        '╱' => '/',
+        '╲' => '\\',
+        '╳' => 'X',
+        '▲' | '↑' => '^',
+        '▼' | '↓' => 'v',
+        '◀' | '←' => '<',
+        '▶' | '→' => '>',
+        '●' => 'o',
+        '◆' => '*',
+        _ => ch,
+    }
+}
+
+fn diagonals_for_char(ch: char) -> Option<u8> {
+    match ch {
+        '╱' => Some(RISING),
+        '╲' => Some(FALLING),
+        '╳' => Some(RISING_AND_FALLING),
+        '/' => Some(RISING | SLASHES),
+        '\\' => Some(FALLING | SLASHES),
+        _ => None,
+    }
+}
+
+fn dirs_for_box_char(ch: char) -> Option<(u8, LineStyle)> {
+    let tables = [
+        (BOX_CHARS, LineStyle::Light),
+        (ROUNDED_CHARS, LineStyle::Rounded),
+        (DASHED_CHARS, LineStyle::Dashed),
+    ];
+    tables.iter().find_map(|&(table, line_style)| {
+        table
+            .iter()
+            .find(|&&(c, _)| c == ch)
+            .map(|&(_, dirs)| (dirs, line_style))
+    })
+}
@@ -0,0 +1,142 @@
+//! Finding orthogonal paths for edges that have to get around the
+//! boxes already on the canvas.
+
+use crate::{Point, Rect};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// What a step costs; bends and stepping onto existing lines cost
+/// extra, so the cheapest path is short, straight and clear.
+const STEP_COST: usize = 1;
+const BEND_COST: usize = 4;
+const LINE_COST: usize = 10;
+
+/// Headings: up, down, left, right, and "not moving yet" (at the start).
+const HEADINGS: usize = 5;
+const START: usize = 4;
+
+/// Finds a path from `from` to `to` that stays within `bounds`, using
+/// only horizontal and vertical steps, and never enters a cell inside
+/// one of the `obstacles` (apart from `from` and `to` themselves, which
+/// are often on the edge of a box). `has_lines` reports cells that
+/// already have lines in them, which are avoided where possible.
+///
+/// Returns the points where the path starts, bends and ends, ready for
+/// `draw_polyline`, or `None` if there is no path.
+pub(crate) fn find_route<F>(
+    bounds: Rect,
+    obstacles: &[Rect],
+    from: Point,
+    to: Point,
+    mut has_lines: F,
+) -> Option<Vec<Point>>
+where
+    F: FnMut(Point) -> bool,
+{
+    if !bounds.contains(from) || !bounds.contains(to) {
+        return None;
+    }
+
+    let index = |point: Point| {
+        let cell = (point.row - bounds.row) * bounds.width + (point.column - bounds.column);
+        cell * HEADINGS
+    };
+    let point = |state: usize| {
+        let cell = state / HEADINGS;
+        Point::new(
+            bounds.row + cell / bounds.width,
+            bounds.column + cell % bounds.width,
+        )
+    };
+    let blocked = |p: Point| p != from && p != to && obstacles.iter().any(|r| r.contains(p));
+
+    let states = bounds.height * bounds.width * HEADINGS;
+    let mut costs = vec![usize::MAX; states];
+    let mut prev = vec![None; states];
+    let mut queue = BinaryHeap::new();
+
+    let start = index(from) + START;
+    costs[start] = 0;
+    queue.push(Reverse((0, start)));
+
+    while let Some(Reverse((cost, state))) = queue.pop() {
+        if cost > costs[state] {
+            continue;
+        }
+        let here = point(state);
+        if here == to {
+            return Some(corners(&path(&prev, state, point)));
+        }
+        let heading = state % HEADINGS;
+        for (next_heading, next) in neighbours(here, bounds) {
+            if blocked(next) {
+                continue;
+            }
+            let mut next_cost = cost + STEP_COST;
+            if heading != START && heading != next_heading {
+                next_cost += BEND_COST;
+            }
+            if has_lines(next) {
+                next_cost += LINE_COST;
+            }
+            let next_state = index(next) + next_heading;
+            if next_cost < costs[next_state] {
+                costs[next_state] = next_cost;
+                prev[next_state] = Some(state);
+                queue.push(Reverse((next_cost, next_state)));
+            }
+        }
+    }
+    None
+}
+
+/// The neighbours of `point` within `bounds`, along with the heading
+/// needed to reach each one.
+fn neighbours(point: Point, bounds: Rect) -> impl Iterator<Item = (usize, Point)> {
+    let Point { row, column } = point;
+    let candidates = [
+        (row > bounds.row, Point::new(row.wrapping_sub(1), column)),
+        (row + 1 < bounds.end_row(), Point::new(row + 1, column)),
+        (
+            column > bounds.column,
+            Point::new(row, column.wrapping_sub(1)),
+        ),
+        (
+            column + 1 < bounds.end_column(),
+            Point::new(row, column + 1),
+        ),
+    ];
+    IntoIterator::into_iter(candidates)
+        .enumerate()
+        .filter(|&(_, (ok, _))| ok)
+        .map(|(heading, (_, point))| (heading, point))
+}
+
+fn path<F>(prev: &[Option<usize>], end: usize, point: F) -> Vec<Point>
+where
+    F: Fn(usize) -> Point,
+{
+    let mut states = vec![end];
+    while let Some(state) = prev[*states.last().unwrap()] {
+        states.push(state);
+    }
+    states.into_iter().rev().map(point).collect()
+}
+
+/// Drops the points in the middle of straight runs, leaving the ends
+/// and the bends.
+fn corners(path: &[Point]) -> Vec<Point> {
+    let mut corners: Vec<Point> = path.iter().cloned().take(1).collect();
+    for window in path.windows(3) {
+        let (a, b, c) = (window[0], window[1], window[2]);
+        let straight =
+            (a.row == b.row && b.row == c.row) || (a.column == b.column && b.column == c.column);
+        if !straight {
+            corners.push(b);
+        }
+    }
+    if path.len() > 1 {
+        corners.push(path[path.len() - 1]);
+    }
+    corners
+}
@@ -16,6 +16,21 @@ impl Row {
         }
     }
 
+    /// Builds a row out of cells that may each hold several `char`s
+    /// (e.g., a letter and its combining marks).
+    pub fn from_graphemes<'a, I>(cells: I) -> Row
+    where
+        I: IntoIterator<Item = (&'a str, Style)>,
+    {
+        let mut text = String::new();
+        let mut styles = vec![];
+        for (grapheme, style) in cells {
+            text.push_str(grapheme);
+            styles.extend(grapheme.chars().map(|_| style));
+        }
+        Row { text, styles }
+    }
+
     pub fn write_to<T: Terminal + ?Sized>(&self, term: &mut T) -> term::Result<()> {
         let mut cursor = StyleCursor::new(term)?;
         for (character, &style) in self.text.trim_end().chars().zip(&self.styles) {
@@ -1,6 +1,10 @@
 use crate::style::Style;
 use crate::test_util::expect_debug;
-use crate::{AsciiCanvas, AsciiView};
+use crate::{
+    Alignment, AsciiCanvas, AsciiView, AsciiViewExt, CanvasError, Charset, ControlChars, Font,
+    JunctionRule, LineCell, LineStyle, Marker, Negative, Newline, Pen, Point, Rect, Sanitize,
+    ShiftedView, Transform, Truncate, Wrap,
+};
 
 #[test]
 fn draw_box() {
@@ -79,3 +83,1128 @@ fn shift() {
         .trim(),
     );
 }
+
+#[test]
+fn mixed_weights() {
+    let mut canvas = AsciiCanvas::new(0, 10);
+    {
+        let view = &mut canvas;
+        view.draw_vertical_line_with(0..5, 2, LineStyle::Double);
+        view.draw_vertical_line(0..5, 7);
+        view.draw_horizontal_line_with(1, 0..10, LineStyle::Heavy);
+        view.draw_horizontal_line_with(3, 0..10, LineStyle::Double);
+    }
+    expect_debug(
+        canvas.to_strings(),
+        r#"
+[
+    "  ║    ╷",
+    "╺━╫━━━━┿━╸",
+    "  ║    │",
+    "══╬════╪══",
+    "  ║    ╵",
+]
+"#
+        .trim(),
+    );
+}
+
+#[test]
+fn rounded_and_dashed_box() {
+    let mut canvas = AsciiCanvas::new(0, 10);
+    {
+        let view = &mut canvas;
+        view.draw_vertical_line_with(0..3, 0, LineStyle::Rounded);
+        view.draw_vertical_line_with(0..3, 5, LineStyle::Rounded);
+        view.draw_horizontal_line_with(0, 0..6, LineStyle::Rounded);
+        view.draw_horizontal_line_with(2, 0..6, LineStyle::Dashed);
+    }
+    expect_debug(
+        canvas.to_strings(),
+        r#"
+[
+    "╭────╮",
+    "│    │",
+    "└┄┄┄┄┘",
+]
+"#
+        .trim(),
+    );
+}
+
+#[test]
+fn dashed_line_ends() {
+    let mut canvas = AsciiCanvas::new(0, 6);
+    {
+        let view: &mut dyn AsciiView = &mut canvas;
+        view.draw_horizontal_line_with(0, 0..2, LineStyle::Dashed);
+        view.draw_horizontal_line_with(1, 0..4, LineStyle::Dashed);
+        view.draw_vertical_line_with(2..5, 5, LineStyle::Dashed);
+    }
+    expect_debug(
+        canvas.to_strings(),
+        r#"
+[
+    "┄┄",
+    "┄┄┄┄",
+    "     ┆",
+    "     ┆",
+    "     ┆",
+]
+"#
+        .trim(),
+    );
+}
+
+#[test]
+fn ascii_charset() {
+    let mut canvas = AsciiCanvas::new(0, 10);
+    canvas.set_charset(Charset::Ascii);
+    {
+        let view = &mut canvas;
+        view.draw_vertical_line(0..4, 1);
+        view.draw_vertical_line_with(0..4, 6, LineStyle::Heavy);
+        view.draw_horizontal_line(1, 0..8);
+        view.draw_horizontal_line_with(3, 1..7, LineStyle::Double);
+        view.write_chars(2, 2, "│Hi─".chars(), Style::new());
+    }
+    expect_debug(
+        canvas.to_strings(),
+        r#"
+[
+    " |    |",
+    "-+----+-",
+    " ||Hi-|",
+    " +====+",
+]
+"#
+        .trim(),
+    );
+}
+
+#[test]
+fn lines_after_text() {
+    let mut canvas = AsciiCanvas::new(0, 10);
+    {
+        let view = &mut canvas;
+        view.write_chars(1, 1, "a│b c".chars(), Style::new());
+        view.draw_horizontal_line(1, 0..8);
+        view.draw_vertical_line(0..3, 2);
+        view.draw_vertical_line(0..3, 4);
+    }
+    expect_debug(
+        canvas.to_strings(),
+        r#"
+[
+    "  ╷ ╷",
+    "╶a│b┼c─╴",
+    "  ╵ ╵",
+]
+"#
+        .trim(),
+    );
+
+    canvas.clear_lines();
+    expect_debug(
+        canvas.to_strings(),
+        r#"
+[
+    "",
+    " a│b c",
+    "",
+]
+"#
+        .trim(),
+    );
+}
+
+#[test]
+fn erase_lines() {
+    let mut canvas = AsciiCanvas::new(0, 10);
+    {
+        let view = &mut canvas;
+        view.draw_vertical_line(0..3, 2);
+        view.draw_vertical_line_with(0..3, 5, LineStyle::Heavy);
+        view.draw_horizontal_line(1, 2..8);
+        view.erase_vertical_line(0..3, 5);
+    }
+    expect_debug(
+        canvas.to_strings(),
+        r#"
+[
+    "  ╷",
+    "  ├────╴",
+    "  ╵",
+]
+"#
+        .trim(),
+    );
+
+    {
+        let view = &mut canvas;
+        view.erase_horizontal_line(1, 2..8);
+    }
+    expect_debug(
+        canvas.to_strings(),
+        r#"
+[
+    "  ╷",
+    "  │",
+    "  ╵",
+]
+"#
+        .trim(),
+    );
+}
+
+#[test]
+fn junction_rules() {
+    use crate::style::{FG_BLUE, FG_RED, FG_YELLOW};
+
+    let red = Pen::new(LineStyle::Light, FG_RED);
+    let blue = Pen::new(LineStyle::Heavy, FG_BLUE);
+    let rules = vec![
+        (JunctionRule::FirstWins, FG_RED),
+        (JunctionRule::LastWins, FG_BLUE),
+        (JunctionRule::Priority(vec![FG_RED, FG_BLUE]), FG_RED),
+        (JunctionRule::Fixed(FG_YELLOW), FG_YELLOW),
+    ];
+    for (rule, expected) in rules {
+        let mut canvas = AsciiCanvas::new(0, 5);
+        canvas.set_junction_rule(rule);
+        {
+            let view = &mut canvas;
+            view.draw_vertical_line_with(0..3, 2, red);
+            view.draw_horizontal_line_with(1, 0..5, blue);
+        }
+        assert!(canvas.read_style(0, 2) == FG_RED);
+        assert!(canvas.read_style(1, 0) == FG_BLUE);
+        assert!(canvas.read_style(1, 2) == expected);
+    }
+
+    // lines of a single style keep it, even where they meet or overlap
+    let mut canvas = AsciiCanvas::new(0, 6);
+    canvas.set_junction_rule(JunctionRule::Fixed(FG_YELLOW));
+    {
+        let view = &mut canvas;
+        view.draw_rect(Rect::new(0, 0, 3, 4), red);
+        view.draw_horizontal_line_with(0, 2..6, red);
+    }
+    for &(row, column) in &[(0, 0), (0, 3), (2, 0), (2, 3), (0, 2)] {
+        assert!(canvas.read_style(row, column) == FG_RED);
+    }
+}
+
+#[test]
+fn diagonal_lines() {
+    let mut canvas = AsciiCanvas::new(0, 12);
+    {
+        let view = &mut canvas;
+        view.draw_line(Point::new(0, 0), Point::new(3, 3));
+        view.draw_line(Point::new(3, 4), Point::new(0, 7));
+        view.draw_line(Point::new(0, 8), Point::new(2, 11));
+        view.draw_line(Point::new(0, 0), Point::new(0, 2));
+    }
+    expect_debug(
+        canvas.to_strings(),
+        r#"
+[
+    "╶─╴    /╲",
+    " \    /  ─╲",
+    "  \  /     ╲",
+    "   \/",
+]
+"#
+        .trim(),
+    );
+}
+
+#[test]
+fn line_markers() {
+    let mut canvas = AsciiCanvas::new(0, 10);
+    {
+        let view = &mut canvas;
+        let pen = Pen::from(LineStyle::Light).with_markers(Marker::Dot, Marker::Arrow);
+        view.draw_horizontal_line_with(0, 0..5, pen);
+        view.draw_line_with(Point::new(1, 4), Point::new(1, 0), pen);
+        view.draw_vertical_line_with(0..3, 7, pen.with_markers(Marker::None, Marker::ThinArrow));
+        view.draw_line_with(Point::new(2, 0), Point::new(4, 2), pen);
+    }
+    expect_debug(
+        canvas.to_strings(),
+        r#"
+[
+    "●───▶  ╷",
+    "◀───●  │",
+    "●      ↓",
+    " \",
+    "  ▶",
+]
+"#
+        .trim(),
+    );
+
+    // markers are part of the line, so they go when it is erased
+    {
+        let view = &mut canvas;
+        view.erase_horizontal_line(0, 0..5);
+        view.erase_vertical_line(0..3, 7);
+    }
+    assert_eq!(canvas.to_strings()[0].to_string(), "");
+    canvas.clear_lines();
+    assert!(canvas
+        .to_strings()
+        .iter()
+        .all(|row| row.to_string().is_empty()));
+}
+
+#[test]
+fn frames() {
+    let mut canvas = AsciiCanvas::new(0, 16);
+    {
+        let view = &mut canvas;
+        view.draw_rect(Rect::new(0, 0, 5, 16), LineStyle::Heavy);
+        view.draw_vertical_line(0..5, 6);
+        {
+            let mut inner = view.draw_frame(Rect::new(1, 8, 3, 7), "Title", Alignment::Right);
+            let inner: &mut dyn AsciiView = &mut inner;
+            inner.write_chars(0, 0, "Hi!".chars(), Style::new());
+        }
+        view.draw_frame(Rect::new(1, 1, 3, 5), "Long title", Alignment::Center);
+        view.draw_frame(Rect::new(5, 0, 3, 8), "日本語タイトル", Alignment::Left);
+        view.draw_frame(Rect::new(5, 8, 3, 8), "日", Alignment::Right);
+        view.draw_frame(Rect::new(8, 0, 3, 11), "a\tb", Alignment::Left);
+        view.draw_frame(Rect::new(8, 11, 3, 5), "\x1b", Alignment::Center);
+    }
+    expect_debug(
+        canvas.to_strings(),
+        r#"
+[
+    "┏━━━━━┯━━━━━━━━┓",
+    "┃┌ L ┐│ ┌ Tit ┐┃",
+    "┃│   ││ │Hi!  │┃",
+    "┃└───┘│ └─────┘┃",
+    "┗━━━━━┷━━━━━━━━┛",
+    "┌ 日本 ┐┌── 日 ┐",
+    "│      ││      │",
+    "└──────┘└──────┘",
+    "┌ a     b ┐┌ � ┐",
+    "│         ││   │",
+    "└─────────┘└───┘",
+]
+"#
+        .trim(),
+    );
+}
+
+#[test]
+fn polylines() {
+    let mut canvas = AsciiCanvas::new(0, 12);
+    {
+        let view = &mut canvas;
+        view.draw_rect(Rect::new(0, 0, 3, 4), LineStyle::Light);
+        view.draw_rect(Rect::new(3, 8, 3, 4), LineStyle::Light);
+        let pen = Pen::from(LineStyle::Rounded).with_markers(Marker::None, Marker::Arrow);
+        view.draw_polyline_with(
+            &[
+                Point::new(1, 3),
+                Point::new(1, 6),
+                Point::new(4, 6),
+                Point::new(4, 7),
+            ],
+            pen,
+        );
+        view.draw_polyline(&[Point::new(2, 2), Point::new(5, 2), Point::new(5, 4)]);
+    }
+    expect_debug(
+        canvas.to_strings(),
+        r#"
+[
+    "┌──┐",
+    "│  ├──╮",
+    "└─┬┘  │",
+    "  │   │ ┌──┐",
+    "  │   ╰▶│  │",
+    "  └─╴   └──┘",
+]
+"#
+        .trim(),
+    );
+}
+
+#[test]
+fn routes() {
+    let mut canvas = AsciiCanvas::new(0, 16);
+    let boxes = [
+        Rect::new(0, 0, 3, 5),
+        Rect::new(0, 6, 4, 3),
+        Rect::new(3, 11, 3, 5),
+    ];
+    let route = {
+        let view = &mut canvas;
+        for &rect in &boxes {
+            view.draw_rect(rect, LineStyle::Light);
+        }
+        let pen = Pen::from(LineStyle::Light).with_markers(Marker::None, Marker::Arrow);
+        view.draw_route_with(&boxes, Point::new(2, 2), Point::new(4, 10), pen, false)
+    };
+    assert_eq!(
+        route,
+        Some(vec![Point::new(2, 2), Point::new(4, 2), Point::new(4, 10),])
+    );
+    expect_debug(
+        canvas.to_strings(),
+        r#"
+[
+    "┌───┐ ┌─┐",
+    "│   │ │ │",
+    "└─┬─┘ │ │",
+    "  │   └─┘  ┌───┐",
+    "  └───────▶│   │",
+    "           └───┘",
+]
+"#
+        .trim(),
+    );
+
+    // keeping clear of lines only looks at the canvas, never grows it
+    let mut canvas = AsciiCanvas::new(0, 10);
+    let route = {
+        let view = &mut canvas;
+        view.draw_horizontal_line(0, 0..2);
+        view.draw_route_with(
+            &[],
+            Point::new(0, 3),
+            Point::new(0, 8),
+            LineStyle::Heavy,
+            true,
+        )
+    };
+    assert_eq!(route, Some(vec![Point::new(0, 3), Point::new(0, 8)]));
+    expect_debug(
+        canvas.to_strings(),
+        r#"
+[
+    "╶╴ ╺━━━━╸",
+]
+"#
+        .trim(),
+    );
+}
+
+#[test]
+fn wide_chars() {
+    let mut canvas = AsciiCanvas::new(0, 10);
+    {
+        let view = &mut canvas;
+        view.draw_rect(Rect::new(0, 0, 3, 10), LineStyle::Light);
+        view.write_chars(1, 1, "日本a\u{301}語".chars(), Style::new());
+        view.write_chars(2, 0, "ab".chars(), Style::new());
+        view.write_chars(2, 8, "字".chars(), Style::new());
+        view.write_chars(2, 9, "字".chars(), Style::new());
+    }
+    expect_debug(
+        canvas.to_strings(),
+        r#"
+[
+    "┌────────┐",
+    "│日本á語 │",
+    "ab───────┘",
+]
+"#
+        .trim(),
+    );
+}
+
+#[test]
+fn grapheme_clusters() {
+    let mut canvas = AsciiCanvas::new(0, 10);
+    {
+        let view = &mut canvas;
+        view.write_chars(0, 0, "🇯🇵|👨‍👩‍👧|e\u{301}|".chars(), Style::new());
+        assert_eq!(view.read_char(0, 6), 'e');
+        assert_eq!(view.read_grapheme(0, 6).as_str(), "e\u{301}");
+        assert_eq!(view.read_grapheme(0, 0).as_str(), "🇯🇵");
+    }
+    expect_debug(
+        canvas.to_strings(),
+        r#"
+[
+    "🇯🇵|👨‍👩‍👧|é|",
+]
+"#
+        .trim(),
+    );
+}
+
+#[test]
+fn text_blocks() {
+    let text = "The quick brown fox jumps over the lazy dog.\nSupercalifragilistic!";
+    let mut canvas = AsciiCanvas::new(0, 40);
+    let rows = {
+        let view = &mut canvas;
+        let mut rows = vec![];
+        for &(column, alignment) in &[
+            (0, Alignment::Left),
+            (10, Alignment::Center),
+            (20, Alignment::Right),
+            (30, Alignment::Justify),
+        ] {
+            let rect = Rect::new(0, column, 4, 9);
+            rows.push(view.write_text_block(rect, text, alignment, Wrap::Overflow, Style::new()));
+        }
+        let rect = Rect::new(8, 0, 2, 12);
+        rows.push(view.write_text_block(rect, text, Alignment::Left, Wrap::Ellipsis, Style::new()));
+        for &text in &["", " \n\n"] {
+            rows.push(view.write_text_block(rect, text, Alignment::Left, Wrap::Clip, Style::new()));
+        }
+        let rect = Rect::new(8, 20, 1, 0);
+        rows.push(view.write_text_block(rect, text, Alignment::Left, Wrap::Ellipsis, Style::new()));
+        rows
+    };
+    assert_eq!(rows, vec![8, 8, 8, 8, 2, 0, 0, 0]);
+    expect_debug(
+        canvas.to_strings(),
+        r#"
+[
+    "The quick The quick The quick The quick",
+    "brown fox brown fox brown fox brown fox",
+    "jumps       jumps       jumps jumps",
+    "over the  over the   over the over  the",
+    "lazy dog. lazy dog. lazy dog. lazy dog.",
+    "Supercali Supercali Supercali Supercali",
+    "fragilist fragilist fragilist fragilist",
+    "ic!          ic!          ic! ic!",
+    "The quick",
+    "brown fox…",
+]
+"#
+        .trim(),
+    );
+}
+
+#[test]
+fn truncation() {
+    use crate::style::DIM;
+
+    let path = "src/some/long/path.rs";
+    let mut canvas = AsciiCanvas::new(0, 14);
+    let widths = {
+        let view = &mut canvas;
+        let mut widths = vec![];
+        for (row, &truncate) in [Truncate::End, Truncate::Start, Truncate::Middle]
+            .iter()
+            .enumerate()
+        {
+            widths.push(view.write_chars_fit(
+                row,
+                0,
+                path.chars(),
+                12,
+                truncate,
+                Style::new(),
+                DIM,
+            ));
+        }
+        widths.push(view.write_chars_fit(
+            3,
+            0,
+            "日本語".chars(),
+            4,
+            Truncate::End,
+            Style::new(),
+            DIM,
+        ));
+        widths.push(view.write_chars_fit(
+            4,
+            0,
+            "short".chars(),
+            12,
+            Truncate::End,
+            Style::new(),
+            DIM,
+        ));
+        widths
+    };
+    assert_eq!(widths, vec![12, 12, 12, 3, 5]);
+    assert!(canvas.read_style(0, 11) == DIM);
+    expect_debug(
+        canvas.to_strings(),
+        r#"
+[
+    "src/some/lo…",
+    "…ong/path.rs",
+    "src/so…th.rs",
+    "日…",
+    "short",
+]
+"#
+        .trim(),
+    );
+
+    canvas.set_charset(Charset::Ascii);
+    {
+        let view = &mut canvas;
+        view.write_chars_fit(5, 0, path.chars(), 12, Truncate::Middle, Style::new(), DIM);
+    }
+    assert_eq!(canvas.to_strings()[5].to_string(), "src/s...h.rs");
+}
+
+#[test]
+fn vertical_text() {
+    let mut canvas = AsciiCanvas::new(0, 6);
+    let extent = {
+        let canvas = &mut canvas;
+        let mut view = canvas.shift(1, 1);
+        {
+            let view = &mut view;
+            view.write_chars_vertical(0, 0, "abc".chars(), Style::new());
+            view.write_chars_vertical(1, 2, "日e\u{301}".chars(), Style::new());
+            view.write_chars_vertical(0, 4, "a\tb".chars(), Style::new());
+        }
+        view.close()
+    };
+    assert_eq!(extent, (3, 5));
+    expect_debug(
+        canvas.to_strings(),
+        r#"
+[
+    "",
+    " a   a",
+    " b 日",
+    " c é b",
+]
+"#
+        .trim(),
+    );
+}
+
+#[test]
+fn banners() {
+    let mut canvas = AsciiCanvas::new(0, 24);
+    let size = {
+        let view = &mut canvas;
+        view.write_banner(0, 0, "Hi 42!", &Font::block(), Style::new())
+    };
+    assert_eq!(size, (3, 21));
+    expect_debug(
+        canvas.to_strings(),
+        r#"
+[
+    "█ █ ▀█▀    █ █ ▀▀▄ █",
+    "█▀█  █     ▀▀█ ▄▀  ▀",
+    "▀ ▀ ▀▀▀      ▀ ▀▀▀ ▀",
+]
+"#
+        .trim(),
+    );
+}
+
+#[test]
+fn figlet_fonts() {
+    let mut source = String::from("flf2a$ 2 1 8 0 1\nA test font.\n");
+    let codes = (32..127u32).chain(vec![196, 214, 220, 228, 246, 252, 223]);
+    for ch in codes.filter_map(std::char::from_u32) {
+        let ch = if ch == '@' { '*' } else { ch };
+        source.push_str(&format!("{}{}@\n{}$@@\n", ch, ch, ch));
+    }
+    source.push_str("0x263A smiley\n:)@\n$)@@\n");
+
+    let font = Font::from_figlet(&source).unwrap();
+    assert_eq!(font.height(), 2);
+    assert_eq!(font.render("Hi\u{263A}"), vec!["HHii:)", "H i  )"]);
+    assert!(Font::from_figlet("not a font").is_err());
+    assert!(Font::from_figlet("flf2a$ 2 1 8 0 0\nAA@\n").is_err());
+}
+
+#[test]
+fn sanitized_text() {
+    let mut canvas = AsciiCanvas::new(0, 20);
+    {
+        let view = &mut canvas;
+        view.write_chars(0, 0, "a\x1b[31mb".chars(), Style::new());
+        view.write_chars(1, 1, "ab\tc\td".chars(), Style::new());
+        view.write_char(2, 0, '\x07', Style::new());
+    }
+    canvas.set_sanitize(Sanitize {
+        control: ControlChars::Caret,
+        tab_stop: Some(4),
+        newline: Newline::Space,
+    });
+    {
+        let view = &mut canvas;
+        view.write_chars(3, 0, "a\x1bb\x7f\nc\td".chars(), Style::new());
+    }
+    canvas.set_sanitize(Sanitize::none());
+    {
+        let view = &mut canvas;
+        view.write_chars(4, 0, "a\tb".chars(), Style::new());
+    }
+    let mut rows = canvas.to_strings();
+    assert_eq!(rows.pop().unwrap().to_string(), "a\tb");
+    expect_debug(
+        rows,
+        r#"
+[
+    "a�[31mb",
+    " ab     c       d",
+    "�",
+    "a^[b^? c    d",
+]
+"#
+        .trim(),
+    );
+}
+
+#[test]
+fn text_with_tabs_and_newlines() {
+    let mut canvas = AsciiCanvas::new(0, 16);
+    canvas.set_sanitize(Sanitize {
+        tab_stop: Some(4),
+        ..Sanitize::default()
+    });
+    let end = {
+        let canvas = &mut canvas;
+        let view = &mut canvas.shift(0, 2);
+        let cursor = view.write_text(0, 0, "fn f() {\r\n\tx\t1\n}", Style::new());
+        assert_eq!(cursor, Point::new(2, 1));
+        view.write_text(cursor.row, cursor.column, "\n\ta\nb", Style::new())
+    };
+    assert_eq!(end, Point::new(4, 2));
+    expect_debug(
+        canvas.to_strings(),
+        r#"
+[
+    "  fn f() {",
+    "      x   1",
+    "  }",
+    "      a",
+    "   b",
+]
+"#
+        .trim(),
+    );
+
+    // a policy that keeps tabs still gets them expanded here, to every
+    // 8 columns
+    let mut canvas = AsciiCanvas::new(0, 16);
+    canvas.set_sanitize(Sanitize::none());
+    let end = {
+        let view = &mut canvas;
+        view.write_text(0, 0, "a\tb\n\tc", Style::new())
+    };
+    assert_eq!(end, Point::new(1, 9));
+    expect_debug(
+        canvas.to_strings(),
+        r#"
+[
+    "a       b",
+    "        c",
+]
+"#
+        .trim(),
+    );
+}
+
+#[test]
+fn growing_columns() {
+    let mut canvas = AsciiCanvas::new(2, 0);
+    canvas.set_max_columns(8);
+    {
+        let view = &mut canvas;
+        // reading past the edge finds blanks, and only writes grow
+        assert_eq!(view.read_char(4, 7), ' ');
+        assert!(view.read_style(4, 7) == Style::new());
+        assert_eq!(view.read_lines(4, 7), LineCell::new());
+        assert_eq!(view.columns(), 0);
+        assert_eq!(view.to_strings().len(), 2);
+        view.draw_rect(Rect::new(0, 0, 2, 3), LineStyle::Light);
+        view.write_chars(1, 4, "ab".chars(), Style::new());
+        view.write_chars(2, 5, "日本".chars(), Style::new());
+    }
+    assert_eq!(canvas.columns(), 8);
+
+    // a maximum below the current width leaves every column usable
+    let mut narrowed = AsciiCanvas::new(2, 10);
+    narrowed.set_max_columns(5);
+    narrowed.write_char(0, 7, 'x', Style::new());
+    assert_eq!(narrowed.read_char(0, 7), 'x');
+    assert_eq!(narrowed.columns(), 10);
+    assert!(narrowed.check_bounds(0, 10).is_err());
+    expect_debug(
+        canvas.to_strings(),
+        r#"
+[
+    "┌─┐",
+    "└─┘ ab",
+    "     日",
+]
+"#
+        .trim(),
+    );
+}
+
+#[test]
+fn negative_offsets() {
+    let mut canvas = AsciiCanvas::new(2, 6);
+    canvas.set_max_columns(usize::MAX);
+    let shift = {
+        let canvas = &mut canvas;
+        canvas.write_chars(0, 0, "abc".chars(), Style::new());
+        {
+            let view = &mut canvas.offset(1, -2, Negative::Clip);
+            view.write_chars(0, 0, "12345".chars(), Style::new());
+        }
+        let mut view = canvas.offset(-1, -2, Negative::Grow);
+        {
+            let view = &mut view;
+            view.draw_rect(Rect::new(0, 0, 2, 3), LineStyle::Light);
+            assert_eq!(view.read_char(1, 2), 'a');
+        }
+        view.origin_shift()
+    };
+    assert_eq!(shift, Point::new(1, 2));
+    expect_debug(
+        canvas.to_strings(),
+        r#"
+[
+    "┌─┐",
+    "└─abc",
+    "  345",
+]
+"#
+        .trim(),
+    );
+
+    // a shifted view makes room out of the margin it was shifted by,
+    // and only grows the canvas for the rest
+    let mut canvas = AsciiCanvas::new(3, 6);
+    canvas.set_max_columns(usize::MAX);
+    {
+        let canvas = &mut canvas;
+        let shifted = &mut canvas.shift(2, 2);
+        shifted.write_char(0, 0, 'a', Style::new());
+        {
+            let view = &mut shifted.offset(-1, -1, Negative::Grow);
+            view.write_char(0, 0, 'b', Style::new());
+        }
+        let mut view = shifted.offset(-1, -3, Negative::Grow);
+        view.write_char(0, 0, 'c', Style::new());
+        assert_eq!(view.origin_shift(), Point::new(1, 3));
+    }
+    expect_debug(
+        canvas.to_strings(),
+        r#"
+[
+    "c",
+    "   b",
+    "    a",
+]
+"#
+        .trim(),
+    );
+}
+
+#[test]
+fn clipping() {
+    let mut canvas = AsciiCanvas::new(5, 10);
+    {
+        let canvas = &mut canvas;
+        canvas.write_chars(0, 0, "0123456789".chars(), Style::new());
+        let view = &mut canvas.clip(Rect::new(1, 2, 3, 5));
+        assert_eq!(view.columns(), 5);
+        assert_eq!(view.read_char(0, 5), ' ');
+        view.draw_rect(Rect::new(0, 0, 8, 12), LineStyle::Light);
+        view.write_chars(1, 1, "clipped text".chars(), Style::new());
+        view.write_chars(2, 3, "a日本".chars(), Style::new());
+        view.write_chars(6, 0, "gone".chars(), Style::new());
+    }
+    {
+        let canvas = &mut canvas;
+        assert_eq!(canvas.shift(0, 12).columns(), 0);
+        assert_eq!(canvas.clip(Rect::new(0, 4, 1, 300_000_000)).columns(), 6);
+        // the part of the rectangle past the edge of the canvas is
+        // clipped too
+        let view = &mut canvas.clip(Rect::new(3, 6, 3, 8));
+        assert_eq!(view.columns(), 4);
+        view.draw_horizontal_line(1, 0..8);
+        view.write_chars(0, 2, "x日本".chars(), Style::new());
+    }
+    {
+        // without a maximum width, the whole rectangle is in bounds
+        let mut wide = AsciiCanvas::new(1, 0);
+        wide.set_max_columns(usize::MAX);
+        let view = &mut wide;
+        let columns = view.clip(Rect::new(0, 0, 1, 300_000_000)).columns();
+        assert_eq!(columns, 300_000_000);
+    }
+    expect_debug(
+        canvas.to_strings(),
+        r#"
+[
+    "0123456789",
+    "  ┌────",
+    "  │clip",
+    "  │  a  x",
+    "      ╶───",
+]
+"#
+        .trim(),
+    );
+}
+
+#[test]
+fn fallible_drawing() {
+    let mut canvas = AsciiCanvas::new(3, 6);
+    {
+        let view = &mut canvas;
+        assert_eq!(
+            view.try_write_chars(0, 2, "long text".chars(), Style::new()),
+            Err(CanvasError::OutOfBounds {
+                row: 0,
+                column: 10,
+                columns: 6,
+            })
+        );
+        assert!(view.try_read_char(0, 6).is_err());
+        assert_eq!(
+            view.try_write_chars(0, 2, "text".chars(), Style::new()),
+            Ok(())
+        );
+        let bent = [Point::new(1, 0), Point::new(2, 1)];
+        assert_eq!(
+            view.try_draw_polyline_with(&bent, LineStyle::Light),
+            Err(CanvasError::InvalidGeometry {
+                from: bent[0],
+                to: bent[1],
+            })
+        );
+        assert!(view
+            .try_draw_rect(Rect::new(1, 2, 2, 5), LineStyle::Light)
+            .is_err());
+        assert!(view
+            .try_draw_rect(Rect::new(1, 2, 2, 4), LineStyle::Light)
+            .is_ok());
+        let out_of_bounds = Err(CanvasError::OutOfBounds {
+            row: 0,
+            column: 6,
+            columns: 6,
+        });
+        assert_eq!(
+            view.try_draw_horizontal_line_with(0, 4..7, LineStyle::Light),
+            out_of_bounds
+        );
+        assert!(view
+            .try_draw_vertical_line_with(0..3, 6, LineStyle::Light)
+            .is_err());
+        assert_eq!(
+            view.try_draw_box_char(0, 0, 'x', Style::new()),
+            Err(CanvasError::UnknownGlyph('x'))
+        );
+        view.draw_box_char(0, 0, '┌', Style::new());
+        assert_eq!(view.try_draw_box_char(0, 0, '┘', Style::new()), Ok(()));
+        assert_eq!(
+            view.clip(Rect::new(0, 4, 3, 4))
+                .try_write_char(0, 2, 'x', Style::new()),
+            out_of_bounds
+        );
+        assert_eq!(
+            view.transform(Rect::new(0, 4, 3, 4), Transform::MirrorHorizontal)
+                .try_write_char(0, 1, 'x', Style::new()),
+            out_of_bounds
+        );
+        // none of these fit, so none of them write anything
+        let plain = Style::new();
+        assert!(view
+            .try_write_text(1, 0, "ab\nhello, world", plain)
+            .is_err());
+        assert!(view
+            .try_write_chars_vertical(1, 5, "a日".chars(), plain)
+            .is_err());
+        assert!(view
+            .try_write_banner(0, 0, "Hi", &Font::block(), plain)
+            .is_err());
+        let rect = Rect::new(1, 3, 2, 4);
+        assert!(view
+            .try_write_text_block(rect, "ab cd", Alignment::Right, Wrap::Clip, plain)
+            .is_err());
+        assert!(view
+            .try_write_chars_fit(1, 4, "abcdef".chars(), 3, Truncate::End, plain, plain)
+            .is_err());
+        assert!(view.try_draw_frame(rect, "Title", Alignment::Left).is_err());
+        // a transposed view runs out of bounds along its rows
+        assert!(view
+            .transform(Rect::new(0, 4, 2, 4), Transform::Transpose)
+            .try_draw_rect(Rect::new(0, 0, 4, 2), LineStyle::Light)
+            .is_err());
+        let view = &mut view.clip(Rect::new(0, 0, 3, 2));
+        assert!(view
+            .try_write_chars(2, 0, "clipped".chars(), Style::new())
+            .is_ok());
+    }
+    assert_eq!(LineCell::try_from_char('┼'), Ok(LineCell::from_char('┼')));
+    assert_eq!(
+        LineCell::try_from_char('x'),
+        Err(CanvasError::UnknownGlyph('x'))
+    );
+    expect_debug(
+        canvas.to_strings(),
+        r#"
+[
+    "┼ text",
+    "  ┌──┐",
+    "cl└──┘",
+]
+"#
+        .trim(),
+    );
+}
+
+#[test]
+fn read_styles() {
+    use crate::style::{BOLD, FG_RED, UNDERLINE};
+
+    let mut canvas = AsciiCanvas::new(2, 6);
+    {
+        let view = &mut canvas;
+        view.write_chars(0, 0, "red".chars(), FG_RED);
+        let mut view = view.styled(BOLD);
+        let view = &mut view;
+        assert!(view.read_style(0, 1) == FG_RED);
+        assert!(view.read_style(0, 4) == Style::new());
+
+        // underline a cell, keeping its colour
+        let mut view = view.shift(0, 1);
+        let style = view.read_style(0, 0).with(UNDERLINE);
+        let ch = view.read_char(0, 0);
+        view.write_char(0, 0, ch, style);
+    }
+    assert!(canvas.read_style(0, 1) == FG_RED.with(UNDERLINE).with(BOLD));
+    assert!(canvas.read_style(0, 2) == FG_RED);
+}
+
+#[test]
+fn user_views() {
+    /// Writes everything in capitals.
+    struct Shouting<'a>(&'a mut AsciiCanvas);
+
+    impl<'a> AsciiView for Shouting<'a> {
+        fn columns(&self) -> usize {
+            self.0.columns()
+        }
+
+        fn read_char(&mut self, row: usize, column: usize) -> char {
+            self.0.read_char(row, column)
+        }
+
+        fn write_char(&mut self, row: usize, column: usize, ch: char, style: Style) {
+            self.0
+                .write_char(row, column, ch.to_ascii_uppercase(), style)
+        }
+    }
+
+    let mut canvas = AsciiCanvas::new(0, 8);
+    {
+        let mut view = Shouting(&mut canvas);
+        view.write_chars(0, 0, "hey".chars(), Style::new());
+        let mut view: ShiftedView<'_, Shouting<'_>> = view.shift(1, 2);
+        view.write_chars(0, 0, "you".chars(), Style::new());
+        view.draw_horizontal_line(1, 0..3);
+        assert_eq!(view.close(), (2, 4));
+    }
+    expect_debug(
+        canvas.to_strings(),
+        r#"
+[
+    "HEY",
+    "  YOU",
+    "  ╶─╴",
+]
+"#
+        .trim(),
+    );
+}
+
+#[test]
+fn transforms() {
+    let mut canvas = AsciiCanvas::new(0, 24);
+    {
+        // a small tree, laid out top down but drawn left to right
+        let mut view = canvas.transform(Rect::new(0, 0, 5, 12), Transform::Transpose);
+        assert_eq!(view.columns(), 5);
+        view.draw_rect(Rect::new(0, 0, 3, 5), LineStyle::Rounded);
+        let pen = Pen::from(LineStyle::Light).with_markers(Marker::None, Marker::Arrow);
+        view.draw_polyline_with(&[Point::new(2, 2), Point::new(5, 2)], pen);
+        view.draw_polyline_with(&[Point::new(3, 2), Point::new(3, 4), Point::new(5, 4)], pen);
+        view.write_char(0, 2, '↓', Style::new());
+        view.write_char(1, 1, '/', Style::new());
+        assert_eq!(view.read_char(0, 0), '╭');
+        assert_eq!(view.read_char(0, 2), '↓');
+    }
+    {
+        let mut view = canvas.transform(Rect::new(0, 12, 3, 6), Transform::MirrorHorizontal);
+        view.draw_rect(Rect::new(0, 0, 3, 4), LineStyle::Light);
+        view.write_chars(1, 1, "a→".chars(), Style::new());
+    }
+    {
+        let mut view = canvas.transform(Rect::new(0, 18, 3, 5), Transform::Rotate90);
+        view.draw_line(Point::new(0, 0), Point::new(2, 2));
+        view.write_char(0, 2, '▶', Style::new());
+    }
+    {
+        // the rectangle runs past the right edge of the canvas, which
+        // mirroring puts on the left of the view
+        let mut view = canvas.transform(Rect::new(3, 20, 1, 6), Transform::MirrorHorizontal);
+        assert_eq!(view.columns(), 0);
+        assert!(view.try_write_char(0, 0, 'x', Style::new()).is_err());
+        view.write_chars(0, 0, "abcde".chars(), Style::new());
+    }
+    expect_debug(
+        canvas.to_strings(),
+        r#"
+[
+    "╭─╮           ┌──┐    /",
+    "│/│           │←a│   /",
+    "→ ├┬─▶        └──┘  / ▼",
+    "│ ││                 edc",
+    "╰─╯└─▶",
+]
+"#
+        .trim(),
+    );
+
+    // wide characters are not turned, and one that would spill out of
+    // the rectangle becomes a space
+    let mut canvas = AsciiCanvas::new(0, 8);
+    {
+        let view = &mut canvas;
+        for row in 0..3 {
+            view.write_chars(row, 0, "abcdefgh".chars(), Style::new());
+        }
+        view.transform(Rect::new(0, 0, 1, 3), Transform::MirrorHorizontal)
+            .write_chars(0, 0, "日".chars(), Style::new());
+        view.transform(Rect::new(1, 0, 2, 1), Transform::Transpose)
+            .write_chars(0, 0, "日".chars(), Style::new());
+        view.transform(Rect::new(2, 0, 1, 3), Transform::MirrorHorizontal)
+            .write_chars(0, 1, "日".chars(), Style::new());
+    }
+    {
+        let mut wide = AsciiCanvas::new(1, 0);
+        wide.set_max_columns(usize::MAX);
+        let view = &mut wide;
+        let rect = Rect::new(0, 0, 1, 300_000_000);
+        let columns = view.transform(rect, Transform::MirrorVertical).columns();
+        assert_eq!(columns, 300_000_000);
+    }
+    expect_debug(
+        canvas.to_strings(),
+        r#"
+[
+    "ab defgh",
+    " bcdefgh",
+    "a日defgh",
+]
+"#
+        .trim(),
+    );
+}
@@ -0,0 +1,339 @@
+//! Laying out text: measuring it, wrapping it into lines and aligning
+//! those lines.
+
+use std::cmp;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// How to place text within a wider space.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Alignment {
+    #[default]
+    Left,
+    Center,
+    Right,
+    /// Stretches the gaps between words so that the text fills the
+    /// width exactly. The last line of a paragraph, and anything that
+    /// is not a block of words (such as a title), is aligned left.
+    Justify,
+}
+
+impl Alignment {
+    /// How far in to start text of width `len` to align it within
+    /// `room`.
+    pub(crate) fn offset(self, len: usize, room: usize) -> usize {
+        let slack = room.saturating_sub(len);
+        match self {
+            Alignment::Left | Alignment::Justify => 0,
+            Alignment::Center => slack / 2,
+            Alignment::Right => slack,
+        }
+    }
+}
+
+/// What `write_text_block` does with text that does not fit in the
+/// rectangle. In every case, lines are broken between words, and words
+/// too long for a line of their own are broken wherever they must be.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Wrap {
+    /// Drops whatever does not fit.
+    #[default]
+    Clip,
+    /// Drops whatever does not fit, ending the last row with `…` to
+    /// show that something is missing.
+    Ellipsis,
+    /// Carries on writing below the rectangle for as many rows as it
+    /// takes.
+    Overflow,
+}
+
+/// Which part of a string `write_chars_fit` drops when it is too
+/// long.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Truncate {
+    /// Keeps the start: `src/some/lo…`
+    #[default]
+    End,
+    /// Keeps the end: `…ong/path.rs`
+    Start,
+    /// Keeps both ends: `src/so…th.rs`
+    Middle,
+}
+
+/// How the text-writing methods (`write_chars` and friends) treat
+/// characters that would otherwise reach the terminal as control
+/// codes. Left alone, an escape character in user-supplied text could
+/// recolour or move the cursor around the whole output, and a tab or
+/// newline would throw off the layout of everything after it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Sanitize {
+    /// What to write in place of control characters.
+    pub control: ControlChars,
+    /// Expands tabs with spaces up to the next multiple of this many
+    /// columns (counted from the view's left edge). `None` treats a
+    /// tab like any other control character.
+    pub tab_stop: Option<usize>,
+    /// What to do with `\n` (and `\r`).
+    pub newline: Newline,
+}
+
+/// See `Sanitize::control`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ControlChars {
+    /// Writes `�` in their place.
+    #[default]
+    Replace,
+    /// Writes them in caret notation, e.g. `^[` for escape or `^?`
+    /// for delete. Control characters that have no caret form are
+    /// replaced with `�`.
+    Caret,
+    /// Writes them as they are. Only use this for trusted text.
+    Keep,
+}
+
+/// See `Sanitize::newline`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Newline {
+    /// Treats newlines like any other control character.
+    #[default]
+    Control,
+    /// Writes a space in their place.
+    Space,
+    /// Drops them.
+    Drop,
+}
+
+impl Default for Sanitize {
+    fn default() -> Self {
+        Sanitize {
+            control: ControlChars::Replace,
+            tab_stop: Some(8),
+            newline: Newline::Control,
+        }
+    }
+}
+
+impl Sanitize {
+    /// A policy that passes everything through untouched.
+    pub fn none() -> Self {
+        Sanitize {
+            control: ControlChars::Keep,
+            tab_stop: None,
+            newline: Newline::Control,
+        }
+    }
+
+    /// Rewrites `text`, to be written starting at `column`, so that it
+    /// contains no control characters (unless they are to be kept).
+    pub(crate) fn apply(&self, text: &str, column: usize) -> String {
+        let mut result = String::with_capacity(text.len());
+        for ch in text.chars() {
+            match (ch, self.tab_stop) {
+                ('\t', Some(stop)) => {
+                    let stop = cmp::max(stop, 1);
+                    let at = column + str_width(&result);
+                    result.extend((0..stop - at % stop).map(|_| ' '));
+                }
+                ('\n', _) | ('\r', _) if self.newline == Newline::Space => result.push(' '),
+                ('\n', _) | ('\r', _) if self.newline == Newline::Drop => {}
+                _ if ch.is_control() => self.control.push(ch, &mut result),
+                _ => result.push(ch),
+            }
+        }
+        result
+    }
+}
+
+impl ControlChars {
+    fn push(self, ch: char, result: &mut String) {
+        match self {
+            ControlChars::Keep => result.push(ch),
+            ControlChars::Caret if ch < ' ' => {
+                result.push('^');
+                result.push((ch as u8 + b'@') as char);
+            }
+            ControlChars::Caret if ch == '\u{7f}' => result.push_str("^?"),
+            ControlChars::Caret | ControlChars::Replace => result.push_str(REPLACEMENT),
+        }
+    }
+
+    /// Replaces a grapheme cluster containing control characters with
+    /// one that fits in a single cell.
+    pub(crate) fn sanitize_grapheme(self, grapheme: &str) -> Option<&'static str> {
+        if self != ControlChars::Keep && grapheme.chars().any(char::is_control) {
+            Some(REPLACEMENT)
+        } else {
+            None
+        }
+    }
+}
+
+const REPLACEMENT: &str = "\u{fffd}";
+
+/// The number of columns a grapheme cluster takes up in a terminal:
+/// 0 (for a stray combining mark, say), 1, or 2 for wide characters.
+pub(crate) fn text_width(grapheme: &str) -> usize {
+    cmp::min(grapheme.width(), 2)
+}
+
+/// The number of columns a string takes up, cluster by cluster.
+pub(crate) fn str_width(text: &str) -> usize {
+    text.graphemes(true).map(text_width).sum()
+}
+
+/// A line of wrapped text.
+pub(crate) struct Line<'text> {
+    words: Vec<&'text str>,
+    /// Whether this line ends a paragraph (and so is never justified).
+    last: bool,
+}
+
+/// Breaks `text` into lines no wider than `width`. Each `\n` in the
+/// text starts a new paragraph. Blank lines at the end are left out,
+/// so text with no words in it has no lines at all.
+pub(crate) fn wrap(text: &str, width: usize) -> Vec<Line<'_>> {
+    let mut lines = vec![];
+    for paragraph in text.split('\n') {
+        let mut line = Line {
+            words: vec![],
+            last: false,
+        };
+        let mut line_width = 0;
+        for word in paragraph
+            .split_whitespace()
+            .flat_map(|w| break_word(w, width))
+        {
+            let word_width = str_width(word);
+            let gap = if line.words.is_empty() { 0 } else { 1 };
+            if !line.words.is_empty() && line_width + gap + word_width > width {
+                lines.push(line);
+                line = Line {
+                    words: vec![],
+                    last: false,
+                };
+                line_width = 0;
+            } else {
+                line_width += gap;
+            }
+            line.words.push(word);
+            line_width += word_width;
+        }
+        line.last = true;
+        lines.push(line);
+    }
+    let blank = lines
+        .iter()
+        .rev()
+        .take_while(|l| l.words.is_empty())
+        .count();
+    lines.truncate(lines.len() - blank);
+    lines
+}
+
+/// Breaks a word into pieces no wider than `width` (most words come
+/// out whole).
+fn break_word(word: &str, width: usize) -> Vec<&str> {
+    let mut pieces = vec![];
+    let (mut start, mut piece_width) = (0, 0);
+    for (offset, grapheme) in word.grapheme_indices(true) {
+        let grapheme_width = text_width(grapheme);
+        if piece_width > 0 && piece_width + grapheme_width > width {
+            pieces.push(&word[start..offset]);
+            start = offset;
+            piece_width = 0;
+        }
+        piece_width += grapheme_width;
+    }
+    pieces.push(&word[start..]);
+    pieces
+}
+
+impl<'text> Line<'text> {
+    /// Joins the words of the line back up, returning the text along
+    /// with how far in to write it.
+    pub(crate) fn layout(&self, width: usize, alignment: Alignment) -> (usize, String) {
+        let words_width: usize = self.words.iter().map(|w| str_width(w)).sum();
+        let gaps = self.words.len().saturating_sub(1);
+        let mut text = String::new();
+        if alignment == Alignment::Justify && !self.last && gaps > 0 {
+            let spaces = width.saturating_sub(words_width);
+            for (index, word) in self.words.iter().enumerate() {
+                if index > 0 {
+                    // spread the spaces out, giving the leftmost gaps
+                    // any extra
+                    let extra = if index <= spaces % gaps { 1 } else { 0 };
+                    text.extend((0..spaces / gaps + extra).map(|_| ' '));
+                }
+                text.push_str(word);
+            }
+            return (0, text);
+        }
+        for (index, word) in self.words.iter().enumerate() {
+            if index > 0 {
+                text.push(' ');
+            }
+            text.push_str(word);
+        }
+        (alignment.offset(words_width + gaps, width), text)
+    }
+}
+
+/// Shortens `text` as needed to make room for `ellipsis` within
+/// `width`, then appends it.
+pub(crate) fn with_ellipsis(text: &str, width: usize, ellipsis: &str) -> String {
+    let room = width.saturating_sub(str_width(ellipsis));
+    let mut result = String::new();
+    let mut result_width = 0;
+    for grapheme in text.graphemes(true) {
+        result_width += text_width(grapheme);
+        if result_width > room {
+            break;
+        }
+        result.push_str(grapheme);
+    }
+    let mut result = result.trim_end().to_string();
+    result.push_str(ellipsis);
+    result
+}
+
+/// If `text` is wider than `width`, picks the start and end of it to
+/// keep so that they fit in `width` along with an ellipsis of
+/// `ellipsis_width` between them. Returns `None` if it all fits.
+pub(crate) fn truncate(
+    text: &str,
+    width: usize,
+    ellipsis_width: usize,
+    truncate: Truncate,
+) -> Option<(&str, &str)> {
+    if str_width(text) <= width {
+        return None;
+    }
+    let room = width.saturating_sub(ellipsis_width);
+    let (head_room, tail_room) = match truncate {
+        Truncate::End => (room, 0),
+        Truncate::Start => (0, room),
+        Truncate::Middle => (room - room / 2, room / 2),
+    };
+
+    let mut head_end = 0;
+    let mut head_width = 0;
+    for (offset, grapheme) in text.grapheme_indices(true) {
+        head_width += text_width(grapheme);
+        if head_width > head_room {
+            break;
+        }
+        head_end = offset + grapheme.len();
+    }
+
+    let mut tail_start = text.len();
+    let mut tail_width = 0;
+    for (offset, grapheme) in text.grapheme_indices(true).rev() {
+        tail_width += text_width(grapheme);
+        if tail_width > tail_room {
+            break;
+        }
+        tail_start = offset;
+    }
+
+    Some((&text[..head_end], &text[tail_start..]))
+}
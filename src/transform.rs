@@ -0,0 +1,98 @@
+//! Flipping and turning drawings: mapping positions through a
+//! transform, and remapping the characters whose shape depends on
+//! which way round they are (box-drawing characters, arrows and
+//! slashes).
+
+use crate::line::LineCell;
+
+/// A way of flipping or turning what is drawn through a
+/// `TransformView`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Transform {
+    /// Flips left to right, so `┌` becomes `┐`.
+    MirrorHorizontal,
+    /// Flips top to bottom, so `┌` becomes `└`.
+    MirrorVertical,
+    /// Swaps rows and columns, so `─` becomes `│` and a tree drawn top
+    /// down comes out left to right.
+    Transpose,
+    /// Turns a quarter turn clockwise.
+    Rotate90,
+    Rotate180,
+    /// Turns a quarter turn anticlockwise.
+    Rotate270,
+}
+
+impl Transform {
+    /// This transform as a transpose (swapping rows and columns),
+    /// followed by flipping the rows and then the columns; returns
+    /// which of those steps take place.
+    pub(crate) fn steps(self) -> (bool, bool, bool) {
+        match self {
+            Transform::MirrorHorizontal => (false, false, true),
+            Transform::MirrorVertical => (false, true, false),
+            Transform::Transpose => (true, false, false),
+            Transform::Rotate90 => (true, false, true),
+            Transform::Rotate180 => (false, true, true),
+            Transform::Rotate270 => (true, true, false),
+        }
+    }
+
+    /// The transform that undoes this one.
+    pub(crate) fn inverse(self) -> Transform {
+        match self {
+            Transform::Rotate90 => Transform::Rotate270,
+            Transform::Rotate270 => Transform::Rotate90,
+            other => other,
+        }
+    }
+
+    /// Whether rows and columns swap places.
+    pub(crate) fn transposes(self) -> bool {
+        self.steps().0
+    }
+
+    /// Whether rising diagonals (`╱` or `/`) become falling ones (`╲`
+    /// or `\`), and vice versa.
+    pub(crate) fn swaps_diagonals(self) -> bool {
+        let (rows, columns) = self.map_step((-1, 1));
+        rows == columns
+    }
+
+    /// Maps a step of `(rows, columns)`, such as `(-1, 0)` for up.
+    pub(crate) fn map_step(self, (rows, columns): (isize, isize)) -> (isize, isize) {
+        let (transpose, flip_rows, flip_columns) = self.steps();
+        let (rows, columns) = if transpose {
+            (columns, rows)
+        } else {
+            (rows, columns)
+        };
+        let rows = if flip_rows { -rows } else { rows };
+        let columns = if flip_columns { -columns } else { columns };
+        (rows, columns)
+    }
+
+    /// Remaps a character whose shape depends on which way round it is
+    /// (box-drawing characters, diagonals and markers); anything else
+    /// comes back unchanged.
+    pub(crate) fn map_char(self, ch: char) -> char {
+        match LineCell::try_from_char(ch) {
+            Ok(lines) if !lines.is_empty() => lines.transformed(self).to_char(),
+            _ => ch,
+        }
+    }
+
+    /// Like `map_char`, for a grapheme cluster of one character (longer
+    /// clusters come back unchanged).
+    pub(crate) fn map_grapheme(self, grapheme: &str) -> String {
+        let mut chars = grapheme.chars();
+        match (chars.next(), chars.next()) {
+            (Some(ch), None) => self.map_char(ch).to_string(),
+            _ => grapheme.to_string(),
+        }
+    }
+}
+
+/// Steps up, down, left and right, in the order used by the weights of
+/// `LineCell` directions.
+pub(crate) const STEPS: [(isize, isize); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];